@@ -3,12 +3,19 @@ use glob::*;
 use image::{
     DynamicImage,
     GenericImageView,
+    GrayImage,
     ImageBuffer,
+    Luma,
     Pixel,
-    imageops::{self, colorops},
+    Rgb,
+    RgbImage,
+    Rgba,
+    RgbaImage,
+    imageops::{self, colorops, ColorMap},
     io::Reader
 };
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// Given target dimensions and input dimensions, return new
 /// dimensions for resizing the image so that the proportionally
@@ -26,13 +33,23 @@ fn constrained_resize_dims(target: (u32, u32), orig: (u32, u32)) -> (u32, u32) {
         // if the target w:h ratio is < orig w:h ratio
         // aka the target is skinnier than the orig
         // then constrain the height to the target and let width adjust
-        (((orig_width / orig_height) * target_height) as u32, target.1)
+        // never short of the target though, or the crop runs off the edge
+        ((((orig_width / orig_height) * target_height) as u32).max(target.0), target.1)
     } else {
         // else the target is fatter so constrain the width to target
-        (target.0, ((orig_height / orig_width) * target_width) as u32)
+        (target.0, (((orig_height / orig_width) * target_width) as u32).max(target.1))
     }
 }
 
+/// Given target dimensions and the dimensions the input was resized to by
+/// constrained_resize_dims(), return the part of it to keep as (x, y,
+/// width, height): the target's size, in the middle across and from the
+/// top down.
+/// This is the crop step in resize_crop().
+fn crop_rect(target: (u32, u32), resized: (u32, u32)) -> (u32, u32, u32, u32) {
+    ((resized.0 - target.0) / 2, 0, target.0, target.1)
+}
+
 /// Resize and crop the given image to best fit the given dimensions
 fn resize_crop<I: GenericImageView>(
     img: &I,
@@ -46,12 +63,345 @@ where
     let (width, height) = img.dimensions();
     let (resize_w, resize_h) = constrained_resize_dims((w, h), (width, height));
     let resized = imageops::resize(img, resize_w, resize_h, imageops::FilterType::Gaussian);
-    // crop in the middle
-    let x = (resize_w / 2) - (w / 2);
-    let cropped = imageops::crop_imm(&resized, x, 0, w, h);
+    let (x, y, w, h) = crop_rect((w, h), (resize_w, resize_h));
+    let cropped = imageops::crop_imm(&resized, x, y, w, h);
     cropped.to_image()
 }
 
+/// Four gray levels, 0 black to 3 white, for the SSD1306 bitplanes.
+struct Gray4;
+
+impl ColorMap for Gray4 {
+    type Color = Luma<u8>;
+
+    fn index_of(&self, color: &Luma<u8>) -> usize {
+        (color.0[0] as usize * 3 + 127) / 255
+    }
+
+    fn map_color(&self, color: &mut Luma<u8>) {
+        color.0[0] = (self.index_of(color) * 85) as u8;
+    }
+}
+
+/// Pack a 4 level image into two 1bpp planes, high bit of the level
+/// first. Rows are MSB first like the .gray files, so each plane is
+/// ready for an ImageRaw. See microaleph::gray.
+fn bitplanes(img: &GrayImage) -> Vec<u8> {
+    let (width, height) = img.dimensions();
+    let row = (width as usize).div_ceil(8);
+    let plane = row * height as usize;
+    let mut out = vec![0u8; 2 * plane];
+    for (x, y, pixel) in img.enumerate_pixels() {
+        let level = Gray4.index_of(pixel);
+        let byte = y as usize * row + x as usize / 8;
+        let bit = 0x80 >> (x % 8);
+        if level & 2 != 0 {
+            out[byte] |= bit;
+        }
+        if level & 1 != 0 {
+            out[plane + byte] |= bit;
+        }
+    }
+    out
+}
+
+/// The 1bpp pipeline for the e-ink display: gray, a little brighter,
+/// a lot more contrast, then dithered to black and white.
+fn bilevel<I: GenericImageView<Pixel = Rgba<u8>>>(img: &I) -> GrayImage {
+    let img = colorops::grayscale(img);
+    let img = colorops::brighten(&img, 20);
+    let mut out = colorops::contrast(&img, 150.0);
+    colorops::dither(&mut out, &colorops::BiLevel);
+    out
+}
+
+/// The 4 level pipeline for SSD1306 bitplanes, dithered to `Gray4`.
+fn four_level<I: GenericImageView<Pixel = Rgba<u8>>>(img: &I) -> GrayImage {
+    let mut out = colorops::grayscale(img);
+    colorops::dither(&mut out, &Gray4);
+    out
+}
+
+/// x, y, width and height on the source, in source pixels.
+type Crop = (f32, f32, f32, f32);
+
+/// Where the crop starts and ends up for a Ken Burns sequence: the widest
+/// crop with the target's shape at one end of the source, panning to the
+/// other end while zooming in by `zoom`. As (x, y, width, height).
+fn ken_burns_ends(
+    target: (u32, u32),
+    orig: (u32, u32),
+    zoom: f32,
+) -> (Crop, Crop) {
+    let (orig_w, orig_h) = (orig.0 as f32, orig.1 as f32);
+    // the target's shape, as big as it goes on the source
+    let scale = (orig_w / target.0 as f32).min(orig_h / target.1 as f32);
+    let (w, h) = (target.0 as f32 * scale, target.1 as f32 * scale);
+    let (zw, zh) = (w / zoom, h / zoom);
+    // across the long way, staying in the middle of the short way
+    let start = (0.0, 0.0, w, h);
+    let end = if orig_w - w >= 1.0 {
+        (orig_w - zw, (orig_h - zh) / 2.0, zw, zh)
+    } else if orig_h - h >= 1.0 {
+        ((orig_w - zw) / 2.0, orig_h - zh, zw, zh)
+    } else {
+        ((orig_w - zw) / 2.0, (orig_h - zh) / 2.0, zw, zh)
+    };
+    (start, end)
+}
+
+/// `steps` crops from `ken_burns_ends`, eased in and out so the camera
+/// doesn't lurch. The size changes by the same ratio each step so the
+/// zoom looks steady.
+fn ken_burns(
+    target: (u32, u32),
+    orig: (u32, u32),
+    zoom: f32,
+    steps: u32,
+) -> Vec<(u32, u32, u32, u32)> {
+    let (start, end) = ken_burns_ends(target, orig, zoom);
+    (0..steps)
+        .map(|step| {
+            let t = if steps > 1 { step as f32 / (steps - 1) as f32 } else { 0.0 };
+            let eased = t * t * (3.0 - 2.0 * t);
+            let w = start.2 * (end.2 / start.2).powf(eased);
+            let h = start.3 * (end.3 / start.3).powf(eased);
+            let x = start.0 + (end.0 - start.0) * eased;
+            let y = start.1 + (end.1 - start.1) * eased;
+            // whole pixels, still on the source
+            let w = (w.round() as u32).clamp(1, orig.0);
+            let h = (h.round() as u32).clamp(1, orig.1);
+            let x = (x.round() as u32).min(orig.0 - w);
+            let y = (y.round() as u32).min(orig.1 - h);
+            (x, y, w, h)
+        })
+        .collect()
+}
+
+/// What the pictures are for, picked with --gray4 and --tricolour.
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    /// black and white .pngs for the e-ink display
+    Bilevel,
+    /// 4 level .gray2 bitplanes for the SSD1306
+    Gray4,
+    /// black and red .tri bitplanes for SSD1675 panels like the Inky pHAT
+    Tricolour,
+}
+
+impl Format {
+    fn size(self) -> (u32, u32) {
+        match self {
+            Format::Bilevel => (128, 160),
+            //Format::Bilevel => (122, 255),
+            Format::Gray4 => (128, 64),
+            // the SSD1675 is 250x122, but e_ink_hello drives 120 rows of
+            // it, whole bytes of a column
+            Format::Tricolour => (250, 120),
+        }
+    }
+}
+
+/// Tone, dither and save a picture that's already `format`'s size, as
+/// `{stem}` and the extension. Returns the file the device shows.
+fn render(img: &RgbaImage, stem: &str, format: Format) -> String {
+    let png_path = format!("{}.png", stem);
+    let (output_path, saved) = match format {
+        Format::Bilevel => {
+            let saved = bilevel(img).save(&png_path).map_err(|e| format!("{:?}", e));
+            (png_path.clone(), saved)
+        }
+        Format::Gray4 => {
+            let out = four_level(img);
+            out.save(&png_path).ok();
+            let output_path = format!("{}.gray2", stem);
+            let saved = fs::write(&output_path, bitplanes(&out)).map_err(|e| format!("{:?}", e));
+            (output_path, saved)
+        }
+        Format::Tricolour => {
+            let inks = tricolour(img);
+            preview(&inks, img.width(), img.height()).save(&png_path).ok();
+            let output_path = format!("{}.tri", stem);
+            let saved = fs::write(&output_path, ink_planes(&inks, img.width(), img.height()))
+                .map_err(|e| format!("{:?}", e));
+            (output_path, saved)
+        }
+    };
+    println!("Saving {}.", output_path);
+    match saved {
+        Ok(_) => println!("Saved {}!", output_path),
+        Err(e) => {
+            println!("Could not save {}.", output_path);
+            println!("{}", e);
+        }
+    }
+    output_path
+}
+
+/// SplitMix64, so a seed picks the same glimpses whatever else changes.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Somewhere in [0, 1).
+    fn unit(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// A random crop with the target's shape, somewhere between a quarter and
+/// all of the widest one that fits. As (x, y, width, height).
+fn glimpse(rng: &mut Rng, target: (u32, u32), orig: (u32, u32)) -> (u32, u32, u32, u32) {
+    let (orig_w, orig_h) = (orig.0 as f32, orig.1 as f32);
+    let widest = (orig_w / target.0 as f32).min(orig_h / target.1 as f32);
+    let scale = widest * (0.25 + 0.75 * rng.unit());
+    let w = ((target.0 as f32 * scale) as u32).clamp(1, orig.0);
+    let h = ((target.1 as f32 * scale) as u32).clamp(1, orig.1);
+    let x = (rng.unit() * (orig.0 - w) as f32) as u32;
+    let y = (rng.unit() * (orig.1 - h) as f32) as u32;
+    (x, y, w, h)
+}
+
+/// How much is going on in a gray image, 0 to 1: the share of pixels
+/// that differ from their right or lower neighbour by more than a little.
+/// Sky, walls and out of focus blur score near 0.
+fn interest(img: &GrayImage) -> f32 {
+    let (width, height) = img.dimensions();
+    let level = |x, y| img.get_pixel(x, y).0[0] as i32;
+    let mut edges = 0;
+    for y in 0..height.saturating_sub(1) {
+        for x in 0..width.saturating_sub(1) {
+            let here = level(x, y);
+            if (here - level(x + 1, y)).abs() > 24 || (here - level(x, y + 1)).abs() > 24 {
+                edges += 1;
+            }
+        }
+    }
+    edges as f32 / (width.max(2) - 1) as f32 / (height.max(2) - 1) as f32
+}
+
+/// The three inks on an SSD1675 panel.
+#[derive(Clone, Copy, PartialEq)]
+enum Ink {
+    White,
+    Black,
+    Red,
+}
+
+/// Each ink and roughly what it looks like on the panel, in sRGB. The red
+/// is darker and duller than a screen's.
+const INKS: [(Ink, [u8; 3]); 3] = [
+    (Ink::White, [255, 255, 255]),
+    (Ink::Black, [0, 0, 0]),
+    (Ink::Red, [190, 30, 35]),
+];
+
+// how much more a difference in colour counts than one in lightness
+const CHROMA: f32 = 10.0;
+
+/// sRGB to OKLab, where the distance between two colours is close to how
+/// different they look.
+fn oklab(rgb: [u8; 3]) -> [f32; 3] {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(rgb[0]), linear(rgb[1]), linear(rgb[2]));
+    let l = (0.4122215 * r + 0.5363325 * g + 0.05144599 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.107397 * b).cbrt();
+    let s = (0.08830246 * r + 0.2817188 * g + 0.6299787 * b).cbrt();
+    [
+        0.2104543 * l + 0.7936178 * m - 0.004072047 * s,
+        1.977998 * l - 2.428592 * m + 0.4505937 * s,
+        0.02590404 * l + 0.7827718 * m - 0.8086758 * s,
+    ]
+}
+
+/// Floyd-Steinberg dither to the panel's three inks, in OKLab so the
+/// error passed on is the difference eyes see. Row by row, like the
+/// image buffer.
+fn tricolour<I: GenericImageView<Pixel = Rgba<u8>>>(img: &I) -> Vec<Ink> {
+    let (width, height) = img.dimensions();
+    let (width, height) = (width as usize, height as usize);
+    let inks: Vec<(Ink, [f32; 3])> = INKS.iter().map(|&(ink, rgb)| (ink, oklab(rgb))).collect();
+    let mut lab: Vec<[f32; 3]> = img
+        .pixels()
+        .map(|(_, _, p)| oklab([p.0[0], p.0[1], p.0[2]]))
+        .collect();
+    let mut out = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let here = lab[y * width + x];
+            // colour counts for more than lightness, or every mid gray
+            // comes out speckled red, red being a mid tone
+            let distance = |c: &[f32; 3]| {
+                let chroma = (here[1] - c[1]).powi(2) + (here[2] - c[2]).powi(2);
+                (here[0] - c[0]).powi(2) + CHROMA * chroma
+            };
+            let &(ink, c) = inks
+                .iter()
+                .min_by(|a, b| distance(&a.1).partial_cmp(&distance(&b.1)).unwrap())
+                .unwrap();
+            out.push(ink);
+            // most of the error, some is lost so runs of it don't smear
+            let error = [0.9 * (here[0] - c[0]), 0.9 * (here[1] - c[1]), 0.9 * (here[2] - c[2])];
+            let mut spread = |dx: isize, dy: usize, share: f32| {
+                let nx = x as isize + dx;
+                if nx >= 0 && (nx as usize) < width && y + dy < height {
+                    let next = &mut lab[(y + dy) * width + nx as usize];
+                    for i in 0..3 {
+                        next[i] += error[i] * share;
+                    }
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+    }
+    out
+}
+
+/// The black plane then the red one, 1bpp rows MSB first like the
+/// .gray files, a bit set where that ink goes. White is neither.
+fn ink_planes(inks: &[Ink], width: u32, height: u32) -> Vec<u8> {
+    let row = (width as usize).div_ceil(8);
+    let plane = row * height as usize;
+    let mut out = vec![0u8; 2 * plane];
+    for (i, &ink) in inks.iter().enumerate() {
+        let (x, y) = (i % width as usize, i / width as usize);
+        let byte = y * row + x / 8;
+        let bit = 0x80 >> (x % 8);
+        match ink {
+            Ink::Black => out[byte] |= bit,
+            Ink::Red => out[plane + byte] |= bit,
+            Ink::White => {}
+        }
+    }
+    out
+}
+
+/// What the panel will look like, near enough.
+fn preview(inks: &[Ink], width: u32, height: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| {
+        let ink = inks[(y * width + x) as usize];
+        let &(_, rgb) = INKS.iter().find(|&&(i, _)| i == ink).unwrap();
+        Rgb(rgb)
+    })
+}
+
 fn input_files(input_glob: &str) -> impl Iterator<Item = PathBuf> {
     glob(input_glob).unwrap().flatten()
 }
@@ -73,10 +423,98 @@ fn main() {
                 .index(2)
                 .help("Output directory"),
         )
+        .arg(
+            Arg::with_name("GRAY4")
+                .long("gray4")
+                .help("4 level 128x64 .gray2 bitplanes for the SSD1306, with a .png preview"),
+        )
+        .arg(
+            Arg::with_name("TRICOLOUR")
+                .long("tricolour")
+                .conflicts_with("GRAY4")
+                .help("Black, white and red 250x120 .tri bitplanes for SSD1675 panels \
+                       like the Inky pHAT, with a .png preview"),
+        )
+        .arg(
+            Arg::with_name("KEN_BURNS")
+                .long("ken-burns")
+                .value_name("STEPS")
+                .help("A run of STEPS frames panning and zooming across each input, \
+                       with a .frames list of how long each is up"),
+        )
+        .arg(
+            Arg::with_name("ZOOM")
+                .long("zoom")
+                .value_name("ZOOM")
+                .default_value("1.5")
+                .help("How far --ken-burns zooms in by its last frame"),
+        )
+        .arg(
+            Arg::with_name("DURATION")
+                .long("duration")
+                .value_name("MS")
+                .default_value("5000")
+                .help("How long each --ken-burns frame is up, the last one twice that"),
+        )
+        .arg(
+            Arg::with_name("GLIMPSES")
+                .long("glimpses")
+                .value_name("N")
+                .conflicts_with("KEN_BURNS")
+                .help("N random crops of each input instead of one from the middle, \
+                       named for where on the input they came from"),
+        )
+        .arg(
+            Arg::with_name("SEED")
+                .long("seed")
+                .value_name("SEED")
+                .default_value("1")
+                .help("Picks the --glimpses, the same seed picks the same ones"),
+        )
+        .arg(
+            Arg::with_name("MIN_INTEREST")
+                .long("min-interest")
+                .value_name("SCORE")
+                .default_value("0.05")
+                .help("How busy a --glimpses crop has to be to keep, 0 to 1"),
+        )
         .get_matches();
 
     let input_glob = matches.value_of("GLOB").unwrap();
     let output_dir = matches.value_of("OUTDIR").unwrap();
+    let format = if matches.is_present("GRAY4") {
+        Format::Gray4
+    } else if matches.is_present("TRICOLOUR") {
+        Format::Tricolour
+    } else {
+        Format::Bilevel
+    };
+    let steps: Option<u32> = matches
+        .value_of("KEN_BURNS")
+        .map(|x| x.parse().expect("--ken-burns takes a number of frames"));
+    let zoom: f32 = matches
+        .value_of("ZOOM")
+        .and_then(|x| x.parse().ok())
+        .filter(|&x| x >= 1.0)
+        .expect("--zoom takes a number, 1 or more");
+    let duration: u32 = matches
+        .value_of("DURATION")
+        .and_then(|x| x.parse().ok())
+        .expect("--duration takes milliseconds");
+    let glimpses: Option<u32> = matches
+        .value_of("GLIMPSES")
+        .map(|x| x.parse().expect("--glimpses takes a number of crops"));
+    let seed: u64 = matches
+        .value_of("SEED")
+        .and_then(|x| x.parse().ok())
+        .expect("--seed takes a number");
+    let min_interest: f32 = matches
+        .value_of("MIN_INTEREST")
+        .and_then(|x| x.parse().ok())
+        .expect("--min-interest takes a number");
+    let mut rng = Rng(seed);
+
+    let (w, h) = format.size();
 
     for (i, file) in input_files(input_glob).enumerate() {
 
@@ -85,7 +523,7 @@ fn main() {
             .as_path()
             .file_stem()
             .and_then(|x| x.to_str())
-            .map(|y| String::from(y))
+            .map(String::from)
             .unwrap_or(String::from("foo"));
 
         let img_opt: Option<DynamicImage> =
@@ -95,27 +533,64 @@ fn main() {
             .and_then(|x| x.decode().ok());
 
         match img_opt {
-            Some(img) => {
-                let output_path = format!("{}/{:03}.{}.png", output_dir, i, filename);
-                println!("Creating {}.", output_path);
-                let img = resize_crop(&img, 128, 160);
-                //let img = resize_crop(&img, 122, 255);
-                //let img = resize_crop(&img, 128, 64);
-                let img = colorops::grayscale(&img);
-                let img = colorops::brighten(&img, 20);
-                let mut out = colorops::contrast(&img, 150.0);
-                colorops::dither(&mut out, &colorops::BiLevel);
-                println!("Saving {}.", output_path);
-                match out.save(&output_path) {
-                    Ok(_) => {
-                        println!("Saved {}!", output_path);
-                    },
+            Some(img) if steps.is_some() => {
+                let steps = steps.unwrap();
+                let stem = format!("{}/{:03}.{}", output_dir, i, filename);
+                println!("Creating {} frames of {}.", steps, stem);
+                let mut frames = String::new();
+                for (step, (x, y, cw, ch)) in
+                    ken_burns((w, h), img.dimensions(), zoom, steps).into_iter().enumerate()
+                {
+                    let img = resize_crop(&img.view(x, y, cw, ch), w, h);
+                    let saved = render(&img, &format!("{}.{:03}", stem, step), format);
+                    // linger on the last one before the next story
+                    let ms = if step as u32 + 1 == steps { 2 * duration } else { duration };
+                    // next to the list, so it's just the name
+                    let name = Path::new(&saved).file_name().and_then(|x| x.to_str());
+                    frames.push_str(&format!("{} {}\n", name.unwrap_or(&saved), ms));
+                }
+                let frames_path = format!("{}.frames", stem);
+                match fs::write(&frames_path, frames) {
+                    Ok(_) => println!("Saved {}!", frames_path),
                     Err(e) => {
-                        println!("Could not save {}.", output_path);
+                        println!("Could not save {}.", frames_path);
                         println!("{:?}", e);
                     }
                 }
             },
+            Some(img) if glimpses.is_some() => {
+                let glimpses = glimpses.unwrap();
+                println!("Creating {} glimpses of {}.", glimpses, filename);
+                let mut kept = 0;
+                // give up on a picture that's boring all over
+                for _ in 0..glimpses * 20 {
+                    if kept == glimpses {
+                        break;
+                    }
+                    let (x, y, cw, ch) = glimpse(&mut rng, (w, h), img.dimensions());
+                    let img = resize_crop(&img.view(x, y, cw, ch), w, h);
+                    let score = interest(&colorops::grayscale(&img));
+                    if score < min_interest {
+                        continue;
+                    }
+                    let stem = format!(
+                        "{}/{:03}.{}.{:02}.{}_{}_{}x{}",
+                        output_dir, i, filename, kept, x, y, cw, ch
+                    );
+                    println!("Interest {:.2} at {},{} {}x{}.", score, x, y, cw, ch);
+                    render(&img, &stem, format);
+                    kept += 1;
+                }
+                if kept < glimpses {
+                    println!("Only found {} interesting glimpses of {}.", kept, filename);
+                }
+            },
+            Some(img) => {
+                let stem = format!("{}/{:03}.{}", output_dir, i, filename);
+                println!("Creating {}.", stem);
+                let img = resize_crop(&img, w, h);
+                render(&img, &stem, format);
+            },
             None => {
                 println!("Could not open {}.", file.to_str().unwrap_or("unknown"));
                 continue;
@@ -125,3 +600,198 @@ fn main() {
     }
     println!("Done!");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crop(target: (u32, u32), orig: (u32, u32)) -> ((u32, u32), (u32, u32, u32, u32)) {
+        let resized = constrained_resize_dims(target, orig);
+        (resized, crop_rect(target, resized))
+    }
+
+    #[test]
+    fn wide_pictures_lose_their_sides() {
+        // a 4:3 photo for the 128x160 e-ink display
+        assert_eq!(crop((128, 160), (4000, 3000)), ((213, 160), (42, 0, 128, 160)));
+        // and a 16:9 one for the SSD1306
+        assert_eq!(crop((128, 64), (1920, 1080)), ((128, 72), (0, 0, 128, 64)));
+    }
+
+    #[test]
+    fn tall_pictures_lose_their_bottoms() {
+        assert_eq!(crop((250, 120), (3000, 4000)), ((250, 333), (0, 0, 250, 120)));
+        assert_eq!(crop((128, 160), (600, 1200)), ((128, 256), (0, 0, 128, 160)));
+    }
+
+    #[test]
+    fn the_right_shape_is_only_resized() {
+        assert_eq!(crop((128, 160), (256, 320)), ((128, 160), (0, 0, 128, 160)));
+        assert_eq!(crop((128, 64), (128, 64)), ((128, 64), (0, 0, 128, 64)));
+    }
+
+    #[test]
+    fn crops_stay_on_the_resized_picture() {
+        for &target in &[(128, 160), (128, 64), (250, 120)] {
+            for &orig in &[(1, 1), (129, 161), (333, 100), (100, 333), (4001, 2999)] {
+                let ((rw, rh), (x, y, w, h)) = crop(target, orig);
+                assert_eq!((w, h), target);
+                assert!(x + w <= rw && y + h <= rh, "{:?} from {:?}", target, orig);
+            }
+        }
+    }
+
+    fn glimpses(seed: u64) -> Vec<(u32, u32, u32, u32)> {
+        let mut rng = Rng(seed);
+        (0..8).map(|_| glimpse(&mut rng, (128, 160), (4000, 3000))).collect()
+    }
+
+    #[test]
+    fn a_seed_picks_the_same_glimpses() {
+        assert_eq!(glimpses(7), glimpses(7));
+        assert_ne!(glimpses(7), glimpses(8));
+        for (x, y, w, h) in glimpses(7) {
+            // the target's shape, on the source
+            assert!(x + w <= 4000 && y + h <= 3000);
+            assert!((w as f32 / h as f32 - 0.8).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn detail_is_more_interesting_than_a_flat_sky() {
+        let flat = GrayImage::from_pixel(64, 64, Luma([128]));
+        // a gentle gradient is still flat, pixel to pixel
+        let gradient = GrayImage::from_fn(64, 64, |x, _| Luma([x as u8 * 2]));
+        let checks = GrayImage::from_fn(64, 64, |x, y| Luma([((x / 4 + y / 4) % 2) as u8 * 255]));
+        let noise = {
+            let mut rng = Rng(1);
+            GrayImage::from_fn(64, 64, |_, _| Luma([rng.next() as u8]))
+        };
+        assert_eq!(interest(&flat), 0.0);
+        assert_eq!(interest(&gradient), 0.0);
+        assert!(interest(&checks) > 0.1);
+        assert!(interest(&noise) > interest(&checks));
+        assert!(interest(&noise) <= 1.0);
+    }
+
+    fn whole(crop: Crop) -> (u32, u32, u32, u32) {
+        let (x, y, w, h) = crop;
+        (x.round() as u32, y.round() as u32, w.round() as u32, h.round() as u32)
+    }
+
+    #[test]
+    fn ken_burns_starts_and_ends_at_the_ends() {
+        for &(target, orig) in &[
+            ((128, 64), (4000, 3000)),
+            ((128, 160), (4000, 3000)),
+            ((128, 64), (600, 1200)),
+            ((128, 64), (1280, 640)),
+        ] {
+            let (start, end) = ken_burns_ends(target, orig, 1.5);
+            let frames = ken_burns(target, orig, 1.5, 24);
+            assert_eq!(frames[0], whole(start), "{:?} from {:?}", target, orig);
+            assert_eq!(frames[23], whole(end), "{:?} from {:?}", target, orig);
+        }
+        // the whole width of a 4:3 photo for a 2:1 screen, panning down to
+        // its bottom while zooming in
+        let (start, end) = ken_burns_ends((128, 64), (4000, 3000), 2.0);
+        assert_eq!(whole(start), (0, 0, 4000, 2000));
+        assert_eq!(whole(end), (1000, 2000, 2000, 1000));
+        // and across one that's wider than the screen
+        let (_, end) = ken_burns_ends((128, 64), (6000, 2000), 2.0);
+        assert_eq!(whole(end), (4000, 500, 2000, 1000));
+    }
+
+    #[test]
+    fn ken_burns_crops_stay_on_the_source() {
+        for &target in &[(128, 64), (64, 128), (128, 160)] {
+            for &orig in &[(1, 1), (127, 63), (128, 64), (4000, 3000), (300, 4000)] {
+                for &zoom in &[1.0, 1.5, 4.0] {
+                    for (x, y, w, h) in ken_burns(target, orig, zoom, 30) {
+                        assert!(w >= 1 && h >= 1);
+                        assert!(
+                            x + w <= orig.0 && y + h <= orig.1,
+                            "{:?} from {:?} at {}x",
+                            target,
+                            orig,
+                            zoom
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ken_burns_takes_as_many_steps_as_asked() {
+        for steps in [0, 1, 2, 7, 120] {
+            assert_eq!(ken_burns((128, 64), (4000, 3000), 1.5, steps).len(), steps as usize);
+        }
+        // one step is just the start
+        let (start, _) = ken_burns_ends((128, 64), (4000, 3000), 1.5);
+        assert_eq!(ken_burns((128, 64), (4000, 3000), 1.5, 1), [whole(start)]);
+    }
+
+    #[test]
+    fn gray4_levels_split_into_two_planes() {
+        // black, dark, light and white, then in-betweens that round to
+        // the nearest of those, on 10 pixel rows so there's padding
+        let levels = [0, 85, 170, 255, 42, 43, 128, 212, 213, 255];
+        let img = GrayImage::from_fn(10, 2, |x, y| {
+            Luma([if y == 0 { levels[x as usize] } else { 0 }])
+        });
+        let planes = bitplanes(&img);
+        // 2 bytes a row, 2 rows, 2 planes
+        assert_eq!(planes.len(), 2 * 2 * 2);
+        let (high, low) = planes.split_at(4);
+        // levels 0 1 2 3 0 1 2 2 | 3 3
+        assert_eq!(high, [0b0011_0011, 0b1100_0000, 0, 0]);
+        assert_eq!(low, [0b0101_0100, 0b1100_0000, 0, 0]);
+    }
+
+    // (black, red) pixels in the planes, and whether any pixel has both
+    fn count_planes(planes: &[u8]) -> (u32, u32, bool) {
+        let (black, red) = planes.split_at(planes.len() / 2);
+        let count = |plane: &[u8]| plane.iter().map(|b| b.count_ones()).sum();
+        let both = black.iter().zip(red).any(|(b, r)| b & r != 0);
+        (count(black), count(red), both)
+    }
+
+    #[test]
+    fn each_ink_gets_its_own_plane() {
+        // a column each of white, black and red, then a gray one that
+        // dithers to some mix of them
+        let colours = [[255, 255, 255], [0, 0, 0], [190, 30, 35], [128, 128, 128]];
+        let img = RgbaImage::from_fn(10, 3, |x, _| {
+            let [r, g, b] = colours[(x as usize).min(3)];
+            Rgba([r, g, b, 255])
+        });
+        let planes = ink_planes(&tricolour(&img), 10, 3);
+        // rows of 10 pixels take 2 bytes
+        assert_eq!(planes.len(), 2 * 2 * 3);
+        let (black, red, both) = count_planes(&planes);
+        assert!(!both);
+        assert!(black >= 3 && red >= 3);
+        // the white column and the padding are in neither
+        for row in planes.chunks(2) {
+            assert_eq!(row[0] & 0x80, 0);
+            assert_eq!(row[1] & 0x3f, 0);
+        }
+        assert_eq!(planes[0] & 0x40, 0x40);
+        assert_eq!(planes[6] & 0x20, 0x20);
+    }
+
+    #[test]
+    fn planes_fit_the_ssd1675() {
+        let (width, height) = Format::Tricolour.size();
+        let img = RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([x as u8, (y * 2) as u8, (x + y) as u8, 255])
+        });
+        let planes = ink_planes(&tricolour(&img), width, height);
+        // 250 pixels is 32 bytes a row, the last 6 bits unused
+        assert_eq!(planes.len(), 2 * 32 * 120);
+        let (black, red, both) = count_planes(&planes);
+        assert!(!both);
+        assert!(black + red <= width * height);
+    }
+}
@@ -0,0 +1,491 @@
+// Photo frame mode: the PNGs and .tri files e_ink_gallery_maker makes,
+// one at a time, in order or shuffled, carrying on from the last one
+// after a restart.
+
+use embedded_graphics::prelude::*;
+use ssd1675::Color;
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How big `--tricolour` pictures are, it doesn't say in the file. The
+/// panel's buffer, ROWS and COLS in main.rs turned on their side.
+const TRI_WIDTH: u32 = 250;
+const TRI_HEIGHT: u32 = 120;
+
+/// A picture ready for the panel, one of its three colours a pixel.
+pub struct Picture {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Picture {
+    /// A .tri or any PNG, see `from_planes` and `from_png`.
+    pub fn load(path: &Path) -> io::Result<Picture> {
+        if path.extension().is_some_and(|x| x == "tri") {
+            Picture::from_planes(&fs::read(path)?, TRI_WIDTH, TRI_HEIGHT)
+        } else {
+            Picture::from_png(path)
+        }
+    }
+
+    /// gallery_maker's `--tricolour` bitplanes: black then red, 1bpp rows
+    /// MSB first, a bit set where that ink goes.
+    pub fn from_planes(data: &[u8], width: u32, height: u32) -> io::Result<Picture> {
+        let row = (width as usize).div_ceil(8);
+        let plane = row * height as usize;
+        if data.len() != 2 * plane {
+            let wrong = format!("not a {}x{} .tri", width, height);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, wrong));
+        }
+        let pixels = (0..height as usize)
+            .flat_map(|y| (0..width as usize).map(move |x| (y * row + x / 8, 0x80 >> (x % 8))))
+            .map(|(byte, bit)| {
+                if data[plane + byte] & bit != 0 {
+                    Color::Red
+                } else if data[byte] & bit != 0 {
+                    Color::Black
+                } else {
+                    Color::White
+                }
+            })
+            .collect();
+        Ok(Picture {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Any PNG: dark pixels go black, strongly red ones red, the rest
+    /// white. gallery_maker's are already dithered to black and white.
+    pub fn from_png(path: &Path) -> io::Result<Picture> {
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        // palettes and fewer than 8 bits come out as plain 8 bit samples
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder
+            .read_info()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buf)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let samples = info.color_type.samples();
+        let pixels = buf[..info.buffer_size()]
+            .chunks(samples)
+            .map(|p| match info.color_type {
+                png::ColorType::Rgb | png::ColorType::Rgba => color(p[0], p[1], p[2]),
+                _ => color(p[0], p[0], p[0]),
+            })
+            .collect();
+        Ok(Picture {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// Draw in the middle of `target`, shrunk to fit if it's too big for
+    /// it. Whatever it doesn't cover is left as it was, white after a clear.
+    pub fn draw<D: DrawTarget<Color = Color>>(&self, target: &mut D) -> Result<(), D::Error> {
+        let area = target.bounding_box();
+        let (width, height) = self.fit(area.size);
+        let corner = area.top_left
+            + Point::new(
+                (area.size.width - width) as i32 / 2,
+                (area.size.height - height) as i32 / 2,
+            );
+        // nearest neighbour, gallery_maker's are dithered already
+        let pixels = (0..height).flat_map(|y| {
+            (0..width).map(move |x| {
+                let source = (y * self.height / height) * self.width + x * self.width / width;
+                let point = corner + Point::new(x as i32, y as i32);
+                Pixel(point, self.pixels[source as usize])
+            })
+        });
+        target.draw_iter(pixels)
+    }
+
+    // the size to draw at in `size`, the same shape, never any bigger
+    fn fit(&self, size: Size) -> (u32, u32) {
+        if self.width <= size.width && self.height <= size.height {
+            (self.width, self.height)
+        } else if self.width * size.height > self.height * size.width {
+            (size.width, (self.height * size.width / self.width).max(1))
+        } else {
+            ((self.width * size.height / self.height).max(1), size.height)
+        }
+    }
+}
+
+fn color(r: u8, g: u8, b: u8) -> Color {
+    let (r, g, b) = (r as u32, g as u32, b as u32);
+    if r > 128 && r > 2 * g && r > 2 * b {
+        Color::Red
+    } else if (r * 299 + g * 587 + b * 114) / 1000 < 128 {
+        Color::Black
+    } else {
+        Color::White
+    }
+}
+
+/// The pictures in a directory and which is up next. Where it's up to goes
+/// in a state file after every picture, so a restart carries on there.
+pub struct Gallery {
+    files: Vec<PathBuf>,
+    order: Vec<usize>,
+    next: usize,
+    shuffle: bool,
+    seed: u64,
+    state: PathBuf,
+}
+
+impl Gallery {
+    pub fn open(dir: &Path, shuffle: bool, state: PathBuf) -> io::Result<Gallery> {
+        let all: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect();
+        let is = |path: &Path, ext: &str| path.extension().is_some_and(|x| x == ext);
+        // a .png next to a .tri is only its preview
+        let mut files: Vec<PathBuf> = all
+            .iter()
+            .filter(|path| {
+                is(path, "tri") || is(path, "png") && !all.contains(&path.with_extension("tri"))
+            })
+            .cloned()
+            .collect();
+        if files.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no .png or .tri files",
+            ));
+        }
+        files.sort();
+
+        // "<seed> <file name>", the shuffle it was on and the last one up
+        let saved = fs::read_to_string(&state).unwrap_or_default();
+        let mut saved = saved.trim().splitn(2, ' ');
+        let seed = saved
+            .next()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or_else(clock_seed);
+        let last = saved.next().unwrap_or("");
+
+        let mut gallery = Gallery {
+            files,
+            order: Vec::new(),
+            next: 0,
+            shuffle,
+            seed,
+            state,
+        };
+        gallery.deal();
+        // a picture that's gone since just starts from the top
+        if let Some(i) = gallery
+            .order
+            .iter()
+            .position(|&f| gallery.files[f].file_name().is_some_and(|n| n == last))
+        {
+            gallery.next = i + 1;
+        }
+        Ok(gallery)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// The next picture to put up, and remember it was.
+    pub fn next(&mut self) -> &Path {
+        if self.next >= self.order.len() {
+            // a new shuffle each time round
+            self.seed = Rng(self.seed).next();
+            self.deal();
+            self.next = 0;
+        }
+        let file = &self.files[self.order[self.next]];
+        self.next += 1;
+        let name = file.file_name().and_then(|x| x.to_str()).unwrap_or("");
+        if let Err(e) = fs::write(&self.state, format!("{} {}\n", self.seed, name)) {
+            println!("Could not save {}: {}", self.state.display(), e);
+        }
+        file
+    }
+
+    // the order for this time round, from the seed
+    fn deal(&mut self) {
+        self.order = (0..self.files.len()).collect();
+        if self.shuffle {
+            // Fisher-Yates
+            let mut rng = Rng(self.seed);
+            for i in (1..self.order.len()).rev() {
+                let j = (rng.next() % (i as u64 + 1)) as usize;
+                self.order.swap(i, j);
+            }
+        }
+    }
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(1, |t| t.as_secs())
+}
+
+// SplitMix64, plenty for putting pictures in some order
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a directory of empty pictures, named for the test so they don't trip
+    // over each other
+    fn dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("e_ink_hello-{}", name));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    fn names(gallery: &mut Gallery) -> Vec<String> {
+        (0..gallery.len())
+            .map(|_| {
+                gallery
+                    .next()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    // just enough of a panel to see what got drawn where
+    struct Canvas {
+        size: Size,
+        pixels: Vec<Color>,
+    }
+
+    impl Canvas {
+        fn new(width: u32, height: u32) -> Canvas {
+            let pixels = vec![Color::White; (width * height) as usize];
+            Canvas {
+                size: Size::new(width, height),
+                pixels,
+            }
+        }
+
+        fn at(&self, x: u32, y: u32) -> Color {
+            self.pixels[(y * self.size.width + x) as usize]
+        }
+    }
+
+    impl OriginDimensions for Canvas {
+        fn size(&self) -> Size {
+            self.size
+        }
+    }
+
+    impl DrawTarget for Canvas {
+        type Color = Color;
+        type Error = ();
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), ()>
+        where
+            I: IntoIterator<Item = Pixel<Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                assert!(self.bounding_box().contains(point), "{:?} is off", point);
+                self.pixels[point.y as usize * self.size.width as usize + point.x as usize] = color;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn tri_files_are_the_panels_size() {
+        let dir = dir("tri", &[]);
+        let row = (TRI_WIDTH as usize).div_ceil(8);
+        let mut data = vec![0; 2 * row * TRI_HEIGHT as usize];
+        // top left black, the next one along red, and one red over black
+        data[0] = 0b1010_0000;
+        data[row * TRI_HEIGHT as usize] = 0b0110_0000;
+        let path = dir.join("a.tri");
+        fs::write(&path, &data).unwrap();
+        let picture = Picture::load(&path).unwrap();
+        assert_eq!((picture.width, picture.height), (TRI_WIDTH, TRI_HEIGHT));
+        assert_eq!(
+            picture.pixels[..4],
+            [Color::Black, Color::Red, Color::Red, Color::White]
+        );
+
+        // a 250x122 one from before is the wrong size
+        data.extend(vec![0; 2 * 2 * row]);
+        fs::write(&path, &data).unwrap();
+        let e = Picture::load(&path).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn pngs_come_in_three_colours() {
+        let dir = dir("png", &[]);
+        let path = dir.join("a.png");
+        let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 3, 1);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&[200, 20, 20, 30, 30, 30, 220, 220, 220])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let picture = Picture::load(&path).unwrap();
+        assert_eq!((picture.width, picture.height), (3, 1));
+        assert_eq!(picture.pixels, [Color::Red, Color::Black, Color::White]);
+        // and anything else isn't a picture
+        fs::write(&path, "not a png").unwrap();
+        assert!(Picture::load(&path).is_err());
+    }
+
+    #[test]
+    fn small_pictures_go_in_the_middle() {
+        let picture = Picture {
+            width: 2,
+            height: 2,
+            pixels: vec![Color::Black; 4],
+        };
+        let mut canvas = Canvas::new(6, 4);
+        picture.draw(&mut canvas).unwrap();
+        for y in 0..4 {
+            for x in 0..6 {
+                let inside = (2..4).contains(&x) && (1..3).contains(&y);
+                let expected = if inside { Color::Black } else { Color::White };
+                assert_eq!(canvas.at(x, y), expected, "{}, {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn big_pictures_shrink_to_fit() {
+        // gallery_maker's --bilevel size on this panel, black on the left
+        // half and red on the right
+        let (width, height) = (128, 160);
+        let pixels = (0..width * height)
+            .map(|i| {
+                if i % width < width / 2 {
+                    Color::Black
+                } else {
+                    Color::Red
+                }
+            })
+            .collect();
+        let picture = Picture {
+            width,
+            height,
+            pixels,
+        };
+        let mut canvas = Canvas::new(250, 120);
+        picture.draw(&mut canvas).unwrap();
+        // 96x120, all of it, with white either side
+        let left = (250 - 96) / 2;
+        for y in [0, 60, 119] {
+            assert_eq!(canvas.at(left - 1, y), Color::White);
+            assert_eq!(canvas.at(left, y), Color::Black);
+            assert_eq!(canvas.at(left + 47, y), Color::Black);
+            assert_eq!(canvas.at(left + 48, y), Color::Red);
+            assert_eq!(canvas.at(left + 95, y), Color::Red);
+            assert_eq!(canvas.at(left + 96, y), Color::White);
+        }
+    }
+
+    #[test]
+    fn a_seed_is_always_the_same_shuffle() {
+        let files = [
+            "a.tri", "b.tri", "c.tri", "d.tri", "e.tri", "f.tri", "g.tri", "h.tri",
+        ];
+        let dir = dir("seed", &files);
+        let order = |seed: u64| {
+            let state = dir.join(format!("seed-{}", seed));
+            fs::write(&state, format!("{} \n", seed)).unwrap();
+            names(&mut Gallery::open(&dir, true, state).unwrap())
+        };
+        let first = order(7);
+        assert_eq!(first, order(7));
+        assert_ne!(first, order(8));
+        // all of them, each once
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, files);
+    }
+
+    #[test]
+    fn carries_on_after_the_last_one() {
+        let dir = dir("resume", &["a.tri", "b.tri", "c.tri", "d.tri"]);
+        let state = dir.join(".last");
+        fs::write(&state, "7 b.tri\n").unwrap();
+        let mut gallery = Gallery::open(&dir, false, state.clone()).unwrap();
+        assert!(gallery.next().ends_with("c.tri"));
+        assert_eq!(fs::read_to_string(&state).unwrap(), "7 c.tri\n");
+        assert!(gallery.next().ends_with("d.tri"));
+        // and round again
+        assert!(gallery.next().ends_with("a.tri"));
+
+        // the same goes for a shuffle, carrying on along the seed's order
+        fs::write(&state, "7 \n").unwrap();
+        let order = names(&mut Gallery::open(&dir, true, state.clone()).unwrap());
+        fs::write(&state, format!("7 {}\n", order[1])).unwrap();
+        let mut gallery = Gallery::open(&dir, true, state).unwrap();
+        assert!(gallery.next().ends_with(&order[2]));
+        assert!(gallery.next().ends_with(&order[3]));
+    }
+
+    #[test]
+    fn a_missing_or_bad_state_starts_from_the_top() {
+        let dir = dir("fresh", &["a.tri", "b.png", "b.tri", "c.png"]);
+        let state = dir.join(".last");
+        for saved in [
+            None,
+            Some(""),
+            Some("garbage"),
+            Some("7 gone.tri\n"),
+            Some("\0\x01 b"),
+        ] {
+            match saved {
+                Some(saved) => fs::write(&state, saved).unwrap(),
+                None => {
+                    fs::remove_file(&state).ok();
+                }
+            }
+            let mut gallery = Gallery::open(&dir, false, state.clone()).unwrap();
+            // b.png is only b.tri's preview
+            assert_eq!(
+                names(&mut gallery),
+                ["a.tri", "b.tri", "c.png"],
+                "{:?}",
+                saved
+            );
+        }
+        // and there's nothing to show in an empty one
+        let empty = dir.join("empty");
+        fs::create_dir(&empty).unwrap();
+        let e = Gallery::open(&empty, false, state).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,188 @@
+// The panel's control lines through the GPIO character device, like
+// /dev/gpiochip0. Lines are requested once and handed back when the
+// program ends, and being in the gpio group is enough to get them.
+//
+// BUSY sleeps on the kernel's edge events rather than reading the line
+// over and over: the driver asks "still busy?" in a tight loop, and each
+// ask blocks until the line falls or the timeout's up.
+
+use gpio_cdev::{Chip, EventRequestFlags, LineEventHandle, LineRequestFlags};
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use linux_embedded_hal::CdevPin;
+
+use std::cell::RefCell;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+
+const CONSUMER: &str = "eink_hello";
+
+fn gpio_error(e: gpio_cdev::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+/// An output on `line`, starting out `value`.
+pub fn output(chip: &mut Chip, line: u32, value: u8) -> io::Result<CdevPin> {
+    let handle = chip
+        .get_line(line)
+        .and_then(|l| l.request(LineRequestFlags::OUTPUT, value, CONSUMER))
+        .map_err(gpio_error)?;
+    CdevPin::new(handle).map_err(gpio_error)
+}
+
+/// The panel's chip select. Usually CE0, which spidev drives itself, so
+/// there's nothing to do here, otherwise a line of its own.
+pub enum ChipSelect {
+    Spidev,
+    Line(CdevPin),
+}
+
+impl OutputPin for ChipSelect {
+    type Error = io::Error;
+
+    fn set_low(&mut self) -> io::Result<()> {
+        match self {
+            ChipSelect::Spidev => Ok(()),
+            ChipSelect::Line(pin) => pin.set_low().map_err(gpio_error),
+        }
+    }
+
+    fn set_high(&mut self) -> io::Result<()> {
+        match self {
+            ChipSelect::Spidev => Ok(()),
+            ChipSelect::Line(pin) => pin.set_high().map_err(gpio_error),
+        }
+    }
+}
+
+/// The BUSY line, high while the panel's working.
+pub struct Busy {
+    events: RefCell<LineEventHandle>,
+    timeout: Duration,
+}
+
+impl Busy {
+    pub fn new(chip: &mut Chip, line: u32, timeout: Duration) -> io::Result<Busy> {
+        let events = chip
+            .get_line(line)
+            .and_then(|l| {
+                l.events(LineRequestFlags::INPUT, EventRequestFlags::FALLING_EDGE, CONSUMER)
+            })
+            .map_err(gpio_error)?;
+        Ok(Busy {
+            events: RefCell::new(events),
+            timeout,
+        })
+    }
+
+    // until an event comes in or `ms` is up, poll's timeout, either way
+    // it's back to the line and the deadline
+    fn wait(&self, ms: i32) -> io::Result<()> {
+        let mut events = self.events.borrow_mut();
+        let mut poll = libc::pollfd {
+            fd: events.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut poll, 1, ms) } > 0 {
+            // take it off the queue, the line's read again anyway
+            events.get_event().map_err(gpio_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl InputPin for Busy {
+    type Error = io::Error;
+
+    /// Waits for the line to fall, so it only ever says low, or gives up
+    /// with `TimedOut` when the panel's missing or stuck. Events left over
+    /// from before just mean another look at the line.
+    fn is_high(&self) -> io::Result<bool> {
+        let deadline = Deadline::after(Instant::now(), self.timeout);
+        loop {
+            let high = self.events.borrow().get_value().map_err(gpio_error)? != 0;
+            if !high {
+                return Ok(false);
+            }
+            match deadline.poll_ms(Instant::now()) {
+                Some(ms) => self.wait(ms)?,
+                None => {
+                    let stuck = format!("still busy after {:?}", self.timeout);
+                    println!("{}", stuck);
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, stuck));
+                }
+            }
+        }
+    }
+
+    fn is_low(&self) -> io::Result<bool> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+/// When `Busy` gives up, kept apart from the line and the clock.
+struct Deadline(Option<Instant>);
+
+impl Deadline {
+    /// `timeout` from `now`, or never when that's further than an
+    /// `Instant` goes.
+    fn after(now: Instant, timeout: Duration) -> Deadline {
+        Deadline(now.checked_add(timeout))
+    }
+
+    /// What's left at `now` as poll's timeout in milliseconds, rounded up
+    /// so it doesn't wake just short, or None once it's passed. -1, for
+    /// ever, when there's no deadline.
+    fn poll_ms(&self, now: Instant) -> Option<i32> {
+        let end = match self.0 {
+            Some(end) => end,
+            None => return Some(-1),
+        };
+        let left = end.saturating_duration_since(now);
+        if left.is_zero() {
+            return None;
+        }
+        let ms = left.as_nanos().div_ceil(1_000_000);
+        Some(ms.min(i32::MAX as u128) as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadlines_count_down_in_whole_milliseconds() {
+        let now = Instant::now();
+        let deadline = Deadline::after(now, Duration::from_secs(5));
+        assert_eq!(deadline.poll_ms(now), Some(5000));
+        assert_eq!(
+            deadline.poll_ms(now + Duration::from_millis(1500)),
+            Some(3500)
+        );
+        // a little under a millisecond still waits one
+        assert_eq!(
+            deadline.poll_ms(now + Duration::from_micros(4_999_001)),
+            Some(1)
+        );
+        assert_eq!(deadline.poll_ms(now + Duration::from_secs(5)), None);
+        assert_eq!(deadline.poll_ms(now + Duration::from_secs(6)), None);
+    }
+
+    #[test]
+    fn no_time_is_already_up() {
+        let now = Instant::now();
+        assert_eq!(Deadline::after(now, Duration::ZERO).poll_ms(now), None);
+    }
+
+    #[test]
+    fn long_timeouts_fit_poll() {
+        let now = Instant::now();
+        // about 25 days is as long as one poll goes, then it's another
+        let days = Deadline::after(now, Duration::from_secs(100 * 24 * 60 * 60));
+        assert_eq!(days.poll_ms(now), Some(i32::MAX));
+        // and past what an Instant holds is never
+        assert_eq!(Deadline::after(now, Duration::MAX).poll_ms(now), Some(-1));
+    }
+}
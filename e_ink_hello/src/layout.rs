@@ -0,0 +1,402 @@
+// Where the status screen's widgets go, from a layout file, so the screen
+// can be rearranged without a rebuild. One widget a line, `#` starts a
+// comment:
+//
+//     # widget  at     size    settings
+//     text      1,0            text="Raspberry Pi" font=24 color=red
+//     cpu_temp  1,32           label="CPU " font=14
+//     clock     -1,0           format=%H:%M
+//     disk_bar  1,60   120x8   path=/home
+//
+// `at` is the region's top left corner. A negative x or y counts from the
+// right or bottom edge instead, -1 being the last column or row, and puts
+// the region's far side there. Without a size the region's as big as the
+// widget measures. Quote values with spaces in.
+//
+// The widgets and their settings:
+//
+//     text                 text
+//     cpu_temp, uptime, uname, load, memory, ip
+//     disk                 path, the filesystem it's on (/)
+//     clock                format, strftime's (%H:%M)
+//     command              run, a shell command whose output's shown
+//     memory_bar, disk_bar color of the fill (black), disk_bar's path (/)
+//
+// and all but the bars take label, font (7, 9, 10, 12, 14, 18 or 24 point)
+// and color (black, red or white).
+
+use embedded_graphics::mono_font::MonoFont;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use profont::{
+    PROFONT_10_POINT, PROFONT_12_POINT, PROFONT_14_POINT, PROFONT_18_POINT, PROFONT_24_POINT,
+    PROFONT_7_POINT, PROFONT_9_POINT,
+};
+use ssd1675::Color;
+
+use crate::widgets::{self, Bar, Canvas, Label, Widget};
+
+use std::convert::Infallible;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// What's on the screen without `--layout`.
+const DEFAULT: &str = r#"
+text      1,0    text="Raspberry Pi" font=24
+cpu_temp  1,32   label="CPU Temp: " font=14
+uname     1,76   font=9
+uptime    1,88   font=9
+"#;
+
+const FONTS: [(&str, &MonoFont); 7] = [
+    ("7", &PROFONT_7_POINT),
+    ("9", &PROFONT_9_POINT),
+    ("10", &PROFONT_10_POINT),
+    ("12", &PROFONT_12_POINT),
+    ("14", &PROFONT_14_POINT),
+    ("18", &PROFONT_18_POINT),
+    ("24", &PROFONT_24_POINT),
+];
+
+struct Placed {
+    widget: Box<dyn Widget>,
+    at: Point,
+    size: Option<Size>,
+}
+
+pub struct Layout {
+    placed: Vec<Placed>,
+}
+
+impl Layout {
+    pub fn load(path: &Path) -> io::Result<Layout> {
+        Layout::parse(&fs::read_to_string(path)?).map_err(|e| {
+            let at = format!("{}:{}", path.display(), e);
+            io::Error::new(io::ErrorKind::InvalidData, at)
+        })
+    }
+
+    /// Errors start with the line number.
+    pub fn parse(text: &str) -> Result<Layout, String> {
+        let mut placed = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let at_line = |e: String| format!("{}: {}", n + 1, e);
+            let words = words(line).map_err(at_line)?;
+            if !words.is_empty() {
+                placed.push(place(&words).map_err(at_line)?);
+            }
+        }
+        Ok(Layout { placed })
+    }
+
+    /// Read every widget and draw them on `canvas`, in the file's order, so
+    /// a later one goes over an earlier one.
+    pub fn draw(&mut self, canvas: &mut Canvas) -> Result<(), Infallible> {
+        let screen = canvas.size();
+        for placed in &mut self.placed {
+            placed.widget.update();
+            let size = placed.size.unwrap_or_else(|| placed.widget.measure());
+            let corner = corner(placed.at, size, screen);
+            placed
+                .widget
+                .draw(&mut canvas.cropped(&Rectangle::new(corner, size)))?;
+        }
+        Ok(())
+    }
+}
+
+// the top left of a `size` region placed `at` on a `screen` sized canvas
+fn corner(at: Point, size: Size, screen: Size) -> Point {
+    let from = |at: i32, length: u32, room: u32| {
+        if at < 0 {
+            room as i32 + at + 1 - length as i32
+        } else {
+            at
+        }
+    };
+    Point::new(
+        from(at.x, size.width, screen.width),
+        from(at.y, size.height, screen.height),
+    )
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::parse(DEFAULT).unwrap()
+    }
+}
+
+// a line's words: split at spaces except in double quotes, up to any #
+fn words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                // so "" is a word too
+                word.get_or_insert_with(String::new);
+            }
+            '#' if !quoted => break,
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return Err(String::from("no closing \""));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn place(words: &[String]) -> Result<Placed, String> {
+    let kind = &words[0];
+    let at = words
+        .get(1)
+        .and_then(|at| at.split_once(','))
+        .and_then(|(x, y)| Some(Point::new(x.parse().ok()?, y.parse().ok()?)))
+        .ok_or_else(|| format!("{} wants a place, like 1,0", kind))?;
+    let mut rest = &words[2..];
+    let first = rest.first().filter(|word| !word.contains('='));
+    let size = match first.and_then(|size| size.split_once('x')) {
+        Some((width, height)) => {
+            let size = width.parse().ok().zip(height.parse().ok());
+            let (width, height) = size.ok_or_else(|| format!("{} isn't a size", rest[0]))?;
+            rest = &rest[1..];
+            Some(Size::new(width, height))
+        }
+        None => None,
+    };
+    let mut settings = rest
+        .iter()
+        .map(|setting| {
+            setting
+                .split_once('=')
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .ok_or_else(|| format!("{} isn't a size or a key=value setting", setting))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let widget = widget(kind, &mut settings)?;
+    if let Some((key, _)) = settings.first() {
+        return Err(format!("{} has no setting {}", kind, key));
+    }
+    Ok(Placed { widget, at, size })
+}
+
+fn widget(kind: &str, settings: &mut Vec<(String, String)>) -> Result<Box<dyn Widget>, String> {
+    let mut take = |key: &str| {
+        let i = settings.iter().position(|(k, _)| k == key)?;
+        Some(settings.remove(i).1)
+    };
+    let color = match take("color").as_deref() {
+        None | Some("black") => Color::Black,
+        Some("red") => Color::Red,
+        Some("white") => Color::White,
+        Some(other) => return Err(format!("no colour {}, only black, red or white", other)),
+    };
+
+    if let Some(bar) = kind.strip_suffix("_bar") {
+        let read: Box<dyn FnMut() -> Option<f32>> = match bar {
+            "memory" => Box::new(widgets::memory_used),
+            "disk" => {
+                let path = take("path").unwrap_or_else(|| String::from("/"));
+                Box::new(move || widgets::disk_used(&path))
+            }
+            _ => return Err(format!("no widget {}", kind)),
+        };
+        return Ok(Box::new(Bar::new(read, color)));
+    }
+
+    let read: Box<dyn FnMut() -> Option<String>> = match kind {
+        "text" => {
+            let text = take("text").ok_or("text wants text=")?;
+            Box::new(move || Some(text.clone()))
+        }
+        "cpu_temp" => Box::new(widgets::cpu_temp),
+        "uptime" => Box::new(widgets::uptime),
+        "uname" => Box::new(widgets::uname),
+        "load" => Box::new(widgets::load),
+        "memory" => Box::new(widgets::memory),
+        "disk" => {
+            let path = take("path").unwrap_or_else(|| String::from("/"));
+            Box::new(move || widgets::disk(&path))
+        }
+        "ip" => Box::new(widgets::ip),
+        "clock" => {
+            let format = take("format").unwrap_or_else(|| String::from("%H:%M"));
+            Box::new(move || widgets::clock(&format))
+        }
+        "command" => {
+            let run = take("run").ok_or("command wants run=")?;
+            Box::new(move || widgets::command(&run))
+        }
+        _ => return Err(format!("no widget {}", kind)),
+    };
+    let font = match take("font") {
+        Some(size) => FONTS
+            .iter()
+            .find(|(name, _)| *name == size)
+            .map(|&(_, font)| font)
+            .ok_or_else(|| format!("no {} point font", size))?,
+        None => &PROFONT_9_POINT,
+    };
+    let label = take("label").unwrap_or_default();
+    Ok(Box::new(Label::new(read, label, font, color)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        Layout::parse(text).err().unwrap()
+    }
+
+    // the only one there is
+    fn only(text: &str) -> Placed {
+        let mut layout = Layout::parse(text).unwrap();
+        assert_eq!(layout.placed.len(), 1);
+        layout.placed.remove(0)
+    }
+
+    fn measure(text: &str) -> Size {
+        let mut placed = only(text);
+        placed.widget.update();
+        placed.widget.measure()
+    }
+
+    // what a canvas would put on the panel
+    struct Panel(Vec<Pixel<Color>>);
+
+    impl OriginDimensions for Panel {
+        fn size(&self) -> Size {
+            Size::new(250, 120)
+        }
+    }
+
+    impl DrawTarget for Panel {
+        type Color = Color;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+        where
+            I: IntoIterator<Item = Pixel<Color>>,
+        {
+            self.0.extend(pixels);
+            Ok(())
+        }
+    }
+
+    impl Panel {
+        fn at(&self, x: i32, y: i32) -> Color {
+            let pixel = self
+                .0
+                .iter()
+                .find(|Pixel(point, _)| *point == Point::new(x, y));
+            pixel.unwrap().1
+        }
+    }
+
+    #[test]
+    fn errors_say_which_line() {
+        assert_eq!(error("\n# comment\ntext 1,0\n"), "3: text wants text=");
+        assert_eq!(error("clock"), "1: clock wants a place, like 1,0");
+        assert_eq!(error("clock 1"), "1: clock wants a place, like 1,0");
+        assert_eq!(error("clock a,0"), "1: clock wants a place, like 1,0");
+        assert_eq!(error("uptime 1,0 10xa"), "1: 10xa isn't a size");
+        assert_eq!(
+            error("uptime 1,0 big"),
+            "1: big isn't a size or a key=value setting"
+        );
+        assert_eq!(error("uptime 1,0\nweather 1,8"), "2: no widget weather");
+        assert_eq!(error("cpu_bar 1,0"), "1: no widget cpu_bar");
+        assert_eq!(
+            error("uptime 1,0 colour=red"),
+            "1: uptime has no setting colour"
+        );
+        assert_eq!(error("uptime 1,0 path=/"), "1: uptime has no setting path");
+        assert_eq!(error("uptime 1,0 font=8"), "1: no 8 point font");
+        assert_eq!(
+            error("uptime 1,0 color=blue"),
+            "1: no colour blue, only black, red or white"
+        );
+        assert_eq!(error("command 1,0"), "1: command wants run=");
+        assert_eq!(error("text 1,0 text=\"Pi"), "1: no closing \"");
+
+        // and through a file, after its name
+        let path = std::env::temp_dir().join("e_ink_hello-bad.layout");
+        fs::write(&path, "uptime 1,0\nuptime 1,0 font=8\n").unwrap();
+        let e = Layout::load(&path).err().unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            e.to_string(),
+            format!("{}:2: no 8 point font", path.display())
+        );
+    }
+
+    #[test]
+    fn quotes_keep_spaces_and_hashes() {
+        assert_eq!(
+            words(r#"text 1,0  text="Raspberry Pi # 4" label="" # red"#).unwrap(),
+            ["text", "1,0", "text=Raspberry Pi # 4", "label="]
+        );
+        assert_eq!(words(r#"a"b c"d"#).unwrap(), ["ab cd"]);
+        assert_eq!(words("  # just a comment").unwrap(), Vec::<String>::new());
+        assert!(words(r#"text="Pi"#).is_err());
+    }
+
+    #[test]
+    fn widgets_take_their_settings() {
+        let placed = only("disk_bar 1,60 120x8 path=/home color=red");
+        assert_eq!(placed.at, Point::new(1, 60));
+        assert_eq!(placed.size, Some(Size::new(120, 8)));
+        let placed = only(r#"clock -1,0 format="%H:%M %S""#);
+        assert_eq!(placed.at, Point::new(-1, 0));
+        assert_eq!(placed.size, None);
+        // the default's all there
+        assert_eq!(Layout::default().placed.len(), 4);
+
+        // text measures as the font's cells, the label's in front
+        let pi = measure("text 1,0 text=Pi");
+        let pi_24 = measure("text 1,0 text=Pi font=24");
+        assert!(pi_24.width > pi.width && pi_24.height > pi.height);
+        let labelled = measure(r#"text 1,0 text=Pi label="a: ""#);
+        assert_eq!(labelled.height, pi.height);
+        assert_eq!(labelled.width, pi.width * 5 / 2);
+    }
+
+    #[test]
+    fn negative_places_count_from_the_far_edge() {
+        let screen = Size::new(250, 120);
+        let size = Size::new(10, 8);
+        let at = |x, y| corner(Point::new(x, y), size, screen);
+        assert_eq!(at(1, 0), Point::new(1, 0));
+        assert_eq!(at(-1, 0), Point::new(240, 0));
+        assert_eq!(at(0, -1), Point::new(0, 112));
+        assert_eq!(at(-1, -1), Point::new(240, 112));
+        assert_eq!(at(-5, -9), Point::new(236, 104));
+
+        // a full red bar in the bottom right corner, outline and all
+        let bar = Bar::new(Box::new(|| Some(1.0)), Color::Red);
+        let mut layout = Layout {
+            placed: vec![Placed {
+                widget: Box::new(bar),
+                at: Point::new(-1, -1),
+                size: Some(size),
+            }],
+        };
+        let mut canvas = Canvas::new(screen);
+        layout.draw(&mut canvas).unwrap();
+        let mut panel = Panel(Vec::new());
+        canvas.draw(&mut panel).unwrap();
+        assert_eq!(panel.at(249, 119), Color::Black);
+        assert_eq!(panel.at(240, 112), Color::Black);
+        assert_eq!(panel.at(242, 114), Color::Red);
+        assert_eq!(panel.at(247, 117), Color::Red);
+        assert_eq!(panel.at(239, 119), Color::White);
+        assert_eq!(panel.at(249, 111), Color::White);
+    }
+}
@@ -1,33 +1,40 @@
 // This code taken from the example at
 // https://github.com/wezm/ssd16750
 extern crate linux_embedded_hal;
+use gpio_cdev::Chip;
 use linux_embedded_hal::spidev::{SpiModeFlags, SpidevOptions};
-use linux_embedded_hal::sysfs_gpio::Direction;
 use linux_embedded_hal::Delay;
-use linux_embedded_hal::{Pin, Spidev};
+use linux_embedded_hal::{CdevPin, Spidev};
 
 extern crate ssd1675;
-use ssd1675::{Builder, Color, Dimensions, Display, GraphicDisplay, Rotation};
+use ssd1675::{Builder, Color, Dimensions, Display, GraphicDisplay, Interface, Rotation};
 
 // Graphics
-#[macro_use]
 extern crate embedded_graphics;
-use embedded_graphics::mono_font::MonoTextStyle;
 use embedded_graphics::prelude::*;
-use embedded_graphics::text::Text;
 
 // Font
 extern crate profont;
-use profont::{PROFONT_12_POINT, PROFONT_14_POINT, PROFONT_24_POINT, PROFONT_9_POINT};
 
-use std::process::Command;
+use clap::{App, Arg, ErrorKind};
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::thread::sleep;
 use std::time::Duration;
-use std::{fs, io};
 
-// Activate SPI, GPIO in raspi-config needs to be run with sudo because of some sysfs_gpio
-// permission problems and follow-up timing problems
-// see https://github.com/rust-embedded/rust-sysfs-gpio/issues/5 and follow-up issues
+mod gallery;
+mod gpio;
+mod layout;
+mod widgets;
+mod wiring;
+use gallery::{Gallery, Picture};
+use gpio::{Busy, ChipSelect};
+use layout::Layout;
+use widgets::Canvas;
+use wiring::{Wiring, PROFILES};
+
+// Activate SPI in raspi-config. Being in the spi and gpio groups is enough to run it, like the
+// default pi user is, no sudo needed
 
 //const ROWS: u16 = 212;
 //const COLS: u8 = 104;
@@ -50,43 +57,149 @@ const LUT: [u8; 70] = [
 , 0b0,  0b0,  0b0,  0b0,  0b0,  0b0,    0b0
 ];
 
+// Command line overrides for each wiring setting: name, flag, value and help
+const WIRING: [(&str, &str, &str, &str); 8] = [
+    ("SPI", "spi", "DEVICE", "The spidev device"),
+    ("SPI_HZ", "spi-hz", "HZ", "SPI clock"),
+    ("SPI_MODE", "spi-mode", "MODE", "SPI mode, 0 to 3"),
+    ("GPIOCHIP", "gpiochip", "DEVICE", "The GPIO character device"),
+    ("BUSY", "busy", "BCM", "BUSY line"),
+    ("DC", "dc", "BCM", "Data/command line"),
+    ("RESET", "reset", "BCM", "RESET line"),
+    ("CS", "cs", "BCM", "Chip select line, or none when it's spidev's CE0 or CE1"),
+];
+
+// a full refresh of a 3 colour panel takes about 15 seconds
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+type Panel<'a> = GraphicDisplay<'a, Interface<Spidev, ChipSelect, Busy, CdevPin, CdevPin>>;
+
+// A bad flag, said the way clap says its own, and exit non-zero
+fn invalid(long: &str, e: impl fmt::Display) -> ! {
+    let message = format!("Invalid value for '--{}': {}", long, e);
+    clap::Error::with_description(&message, ErrorKind::InvalidValue).exit()
+}
+
 fn main() -> Result<(), std::io::Error> {
+    let matches = App::new("E-Ink Hello")
+        .about("System stats, or a photo frame, on an Inky pHAT.")
+        .arg(
+            Arg::with_name("GALLERY")
+                .long("gallery")
+                .value_name("DIR")
+                .help("Show the .pngs and .tris in DIR one after another, like e_ink_gallery_maker makes"),
+        )
+        .arg(
+            Arg::with_name("INTERVAL")
+                .long("interval")
+                .value_name("SECS")
+                .default_value("600")
+                .help("How long each picture is up"),
+        )
+        .arg(
+            Arg::with_name("SHUFFLE")
+                .long("shuffle")
+                .help("A different order every time round"),
+        )
+        .arg(
+            Arg::with_name("STATE")
+                .long("state")
+                .value_name("FILE")
+                .help("Where it's up to, to carry on from after a restart [default: DIR/.last]"),
+        )
+        .arg(
+            Arg::with_name("LAYOUT")
+                .long("layout")
+                .value_name("FILE")
+                .help("Which widgets go where on the status screen, see src/layout.rs"),
+        )
+        .arg(
+            Arg::with_name("CONFIG")
+                .long("config")
+                .value_name("FILE")
+                .help("Wiring settings, see src/wiring.rs, on top of --profile"),
+        )
+        .arg(
+            Arg::with_name("PROFILE")
+                .long("profile")
+                .value_name("BOARD")
+                .possible_values(&PROFILES)
+                .help("Which board the panel's on [default: inky-phat]"),
+        )
+        .args(&WIRING.map(|(name, long, value, help)| {
+            Arg::with_name(name).long(long).value_name(value).help(help)
+        }))
+        .get_matches();
+
+    // the profile, then the config file, then any of these
+    let mut wiring = Wiring::default();
+    if let Some(profile) = matches.value_of("PROFILE") {
+        wiring
+            .set("profile", profile)
+            .unwrap_or_else(|e| invalid("profile", e));
+    }
+    if let Some(config) = matches.value_of("CONFIG") {
+        wiring
+            .load(Path::new(config))
+            .unwrap_or_else(|e| invalid("config", e));
+    }
+    for (name, long, _, _) in WIRING {
+        if let Some(value) = matches.value_of(name) {
+            wiring
+                .set(&name.to_lowercase(), value)
+                .unwrap_or_else(|e| invalid(long, e));
+        }
+    }
+    println!("Wiring {:?}", wiring);
+
+    // 0 would redraw the panel nonstop
+    let interval: u64 = matches
+        .value_of("INTERVAL")
+        .and_then(|x| x.parse().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or_else(|| invalid("interval", "takes seconds, 1 or more"));
+    let gallery = match matches.value_of("GALLERY") {
+        Some(dir) => {
+            let dir = Path::new(dir);
+            let state = matches
+                .value_of("STATE")
+                .map_or_else(|| dir.join(".last"), PathBuf::from);
+            let gallery = Gallery::open(dir, matches.is_present("SHUFFLE"), state)?;
+            println!("{} pictures in {}", gallery.len(), dir.display());
+            Some(gallery)
+        }
+        None => None,
+    };
+    let layout = match matches.value_of("LAYOUT") {
+        Some(layout) => Layout::load(Path::new(layout))?,
+        None => Layout::default(),
+    };
+
     // Configure SPI
-    let mut spi = Spidev::open("/dev/spidev0.0").expect("SPI device");
+    let mut spi = Spidev::open(&wiring.spi).expect("SPI device");
+    let mode = match wiring.spi_mode {
+        0 => SpiModeFlags::SPI_MODE_0,
+        1 => SpiModeFlags::SPI_MODE_1,
+        2 => SpiModeFlags::SPI_MODE_2,
+        _ => SpiModeFlags::SPI_MODE_3,
+    };
     let options = SpidevOptions::new()
         .bits_per_word(8)
-        .max_speed_hz(4_000_000)
-        .mode(SpiModeFlags::SPI_MODE_0)
+        .max_speed_hz(wiring.spi_hz)
+        .mode(mode)
         .build();
     spi.configure(&options).expect("SPI configuration");
 
-    // https://pinout.xyz/pinout/inky_phat
     // Configure Digital I/O Pins
-
-    let busy = Pin::new(17); // BCM17
-    busy.export().expect("busy export");
-    while !busy.is_exported() {}
-    busy.set_direction(Direction::In).expect("busy Direction");
-
-    let dc = Pin::new(22); // BCM22
-    dc.export().expect("dc export");
-    while !dc.is_exported() {}
-    dc.set_direction(Direction::Out).expect("dc Direction");
-    dc.set_value(1).expect("dc Value set to 1");
-
-    let reset = Pin::new(27); // BCM27
-    reset.export().expect("reset export");
-    while !reset.is_exported() {}
-    reset
-        .set_direction(Direction::Out)
-        .expect("reset Direction");
-    reset.set_value(1).expect("reset Value set to 1");
-
-    let cs = Pin::new(0); // BCM8
-    cs.export().expect("cs export");
-    while !cs.is_exported() {}
-    cs.set_direction(Direction::Out).expect("CS Direction");
-    cs.set_value(1).expect("CS Value set to 1");
+    let mut chip = Chip::new(&wiring.gpiochip).expect("GPIO chip");
+    let busy = Busy::new(&mut chip, wiring.busy, BUSY_TIMEOUT).expect("busy line");
+    let dc = gpio::output(&mut chip, wiring.dc, 1).expect("dc line");
+    let reset = gpio::output(&mut chip, wiring.reset, 1).expect("reset line");
+    let cs = match wiring.cs {
+        // ssd1675 never toggles it, so the panel's selected throughout
+        Some(line) => ChipSelect::Line(gpio::output(&mut chip, line, 0).expect("cs line")),
+        None => ChipSelect::Spidev,
+    };
 
     println!("Pins configured");
 
@@ -107,64 +220,27 @@ fn main() -> Result<(), std::io::Error> {
         .build()
         .expect("invalid configuration");
     let display = Display::new(controller, config);
-    let mut display = GraphicDisplay::new(display, &mut black_buffer, &mut red_buffer);
+    let mut display = GraphicDisplay::new(display, &mut black_buffer[..], &mut red_buffer[..]);
 
-    // Main loop. Displays CPU temperature, uname, and uptime every minute with a red Raspberry Pi
-    // header.
+    match gallery {
+        Some(gallery) => show_gallery(&mut display, &mut delay, gallery, interval),
+        None => hello(&mut display, &mut delay, layout),
+    }
+}
+
+// The status screen, redrawn every minute.
+fn hello(display: &mut Panel, delay: &mut Delay, mut layout: Layout) -> Result<(), std::io::Error> {
+    let mut canvas = Canvas::new(display.size());
     loop {
-        display.reset(&mut delay).expect("error resetting display");
+        display.reset(delay).expect("error resetting display");
         println!("Reset and initialised");
         let one_minute = Duration::from_secs(60);
 
-        display.clear(Color::White);
-        println!("Clear");
-
-        Text::new(
-            "Raspberry Pi",
-            Point::new(1, 4),
-            MonoTextStyle::new(&PROFONT_24_POINT, Color::Black),
-        )
-        .draw(&mut display)
-        .expect("error drawing text");
-
-        if let Ok(cpu_temp) = read_cpu_temp() {
-            Text::new(
-                "CPU Temp:",
-                Point::new(1, 30),
-                MonoTextStyle::new(&PROFONT_14_POINT, Color::Black),
-            )
-            .draw(&mut display)
-            .expect("error drawing text");
-            Text::new(
-                &format!("{:.1}°C", cpu_temp),
-                Point::new(95, 34),
-                MonoTextStyle::new(&PROFONT_12_POINT, Color::Black),
-            )
-            .draw(&mut display)
-            .expect("error drawing text");
-        }
-
-        if let Some(uptime) = read_uptime() {
-            Text::new(
-                uptime.trim(),
-                Point::new(1, 93),
-                MonoTextStyle::new(&PROFONT_9_POINT, Color::Black),
-            )
-            .draw(&mut display)
-            .expect("error drawing text");
-        }
-
-        if let Some(uname) = read_uname() {
-            Text::new(
-                uname.trim(),
-                Point::new(1, 84),
-                MonoTextStyle::new(&PROFONT_9_POINT, Color::Black),
-            )
-            .draw(&mut display)
-            .expect("error drawing text");
-        }
+        canvas.clear(Color::White).expect("error clearing");
+        layout.draw(&mut canvas).expect("error drawing widgets");
+        canvas.draw(display).expect("error drawing widgets");
 
-        display.update(&mut delay).expect("error updating display");
+        display.update(delay).expect("error updating display");
         println!("Update...");
 
         println!("Finished - going to sleep");
@@ -174,38 +250,36 @@ fn main() -> Result<(), std::io::Error> {
     }
 }
 
-fn read_cpu_temp() -> Result<f64, io::Error> {
-    fs::read_to_string("/sys/class/thermal/thermal_zone0/temp")?
-        .trim()
-        .parse::<i32>()
-        .map(|temp| temp as f64 / 1000.)
-        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
-}
-
-fn read_uptime() -> Option<String> {
-    Command::new("uptime")
-        .arg("-p")
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                String::from_utf8(output.stdout).ok()
-            } else {
-                None
+// A picture every `interval` seconds. The panel sleeps in between, it
+// keeps the picture without power.
+fn show_gallery(
+    display: &mut Panel,
+    delay: &mut Delay,
+    mut gallery: Gallery,
+    interval: u64,
+) -> Result<(), std::io::Error> {
+    let mut failed = 0;
+    loop {
+        let path = gallery.next().to_path_buf();
+        let picture = match Picture::load(&path) {
+            Ok(picture) => picture,
+            // on to the next one rather than a blank panel, unless none open
+            Err(e) if failed + 1 < gallery.len() => {
+                println!("Could not open {}: {}", path.display(), e);
+                failed += 1;
+                continue;
             }
-        })
-}
+            Err(e) => return Err(e),
+        };
+        failed = 0;
 
-fn read_uname() -> Option<String> {
-    Command::new("uname")
-        .arg("-smr")
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                String::from_utf8(output.stdout).ok()
-            } else {
-                None
-            }
-        })
+        display.reset(delay).expect("error resetting display");
+        display.clear(Color::White);
+        picture.draw(display).expect("error drawing picture");
+        display.update(delay).expect("error updating display");
+        println!("Showing {}", path.display());
+        display.deep_sleep()?;
+
+        sleep(Duration::from_secs(interval));
+    }
 }
@@ -0,0 +1,275 @@
+// The status screen's pieces. Each widget reads something about the Pi
+// every time the screen's redrawn, says how much room that takes, and
+// draws it into whatever region layout.rs gives it.
+
+use embedded_graphics::draw_target::Cropped;
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+use embedded_graphics::text::{Baseline, Text};
+use ssd1675::Color;
+
+use std::convert::Infallible;
+use std::ffi::CString;
+use std::fs;
+use std::mem::MaybeUninit;
+use std::process::Command;
+
+/// The whole screen, drawn off the panel so each widget can be cropped to
+/// its region, then put up in one go.
+pub struct Canvas {
+    size: Size,
+    pixels: Vec<Color>,
+}
+
+impl Canvas {
+    pub fn new(size: Size) -> Canvas {
+        let pixels = vec![Color::White; (size.width * size.height) as usize];
+        Canvas { size, pixels }
+    }
+
+    pub fn draw<D: DrawTarget<Color = Color>>(&self, target: &mut D) -> Result<(), D::Error> {
+        target.fill_contiguous(&self.bounding_box(), self.pixels.iter().copied())
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = Color;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+    where
+        I: IntoIterator<Item = Pixel<Color>>,
+    {
+        let area = self.bounding_box();
+        let width = self.size.width as usize;
+        for Pixel(point, color) in pixels {
+            if area.contains(point) {
+                self.pixels[point.y as usize * width + point.x as usize] = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a widget draws: (0, 0) is its top left corner, and anything
+/// outside is cut off.
+pub type Region<'a> = Cropped<'a, Canvas>;
+
+pub trait Widget {
+    /// Read whatever it shows, once a redraw.
+    fn update(&mut self);
+    /// The room it takes to show what it last read.
+    fn measure(&self) -> Size;
+    /// Draw what it last read. The region's what it measured unless the
+    /// layout says otherwise.
+    fn draw(&self, region: &mut Region) -> Result<(), Infallible>;
+}
+
+/// Text, a line or a few, after a label. Nothing at all when there's
+/// nothing to read, like a command that failed.
+pub struct Label {
+    read: Box<dyn FnMut() -> Option<String>>,
+    label: String,
+    style: MonoTextStyle<'static, Color>,
+    shown: Option<String>,
+}
+
+impl Label {
+    pub fn new(
+        read: Box<dyn FnMut() -> Option<String>>,
+        label: String,
+        font: &'static MonoFont<'static>,
+        color: Color,
+    ) -> Label {
+        Label {
+            read,
+            label,
+            style: MonoTextStyle::new(font, color),
+            shown: None,
+        }
+    }
+
+    fn text(&self) -> Option<Text<'_, MonoTextStyle<'static, Color>>> {
+        let shown = self.shown.as_deref()?;
+        Some(Text::with_baseline(shown, Point::zero(), self.style, Baseline::Top))
+    }
+}
+
+impl Widget for Label {
+    fn update(&mut self) {
+        self.shown = (self.read)().map(|text| format!("{}{}", self.label, text.trim_end()));
+    }
+
+    fn measure(&self) -> Size {
+        self.text().map_or(Size::zero(), |text| text.bounding_box().size)
+    }
+
+    fn draw(&self, region: &mut Region) -> Result<(), Infallible> {
+        if let Some(text) = self.text() {
+            text.draw(region)?;
+        }
+        Ok(())
+    }
+}
+
+/// How full something is, as an outlined bar filled in from the left.
+pub struct Bar {
+    read: Box<dyn FnMut() -> Option<f32>>,
+    color: Color,
+    fraction: Option<f32>,
+}
+
+impl Bar {
+    pub fn new(read: Box<dyn FnMut() -> Option<f32>>, color: Color) -> Bar {
+        Bar {
+            read,
+            color,
+            fraction: None,
+        }
+    }
+}
+
+impl Widget for Bar {
+    fn update(&mut self) {
+        self.fraction = (self.read)();
+    }
+
+    fn measure(&self) -> Size {
+        Size::new(100, 8)
+    }
+
+    fn draw(&self, region: &mut Region) -> Result<(), Infallible> {
+        let fraction = match self.fraction {
+            Some(fraction) => fraction.clamp(0.0, 1.0),
+            None => return Ok(()),
+        };
+        let outline = Rectangle::new(Point::zero(), region.bounding_box().size);
+        outline
+            .into_styled(PrimitiveStyle::with_stroke(Color::Black, 1))
+            .draw(region)?;
+        // a pixel's gap inside the outline
+        let inside = outline.offset(-2);
+        let width = (inside.size.width as f32 * fraction).round() as u32;
+        Rectangle::new(inside.top_left, Size::new(width, inside.size.height))
+            .into_styled(PrimitiveStyle::with_fill(self.color))
+            .draw(region)
+    }
+}
+
+// What the widgets read. None when it can't be, and the widget's left off.
+
+pub fn cpu_temp() -> Option<String> {
+    let temp = fs::read_to_string("/sys/class/thermal/thermal_zone0/temp").ok()?;
+    let temp = temp.trim().parse::<i32>().ok()? as f64 / 1000.;
+    Some(format!("{:.1}°C", temp))
+}
+
+pub fn uptime() -> Option<String> {
+    output("uptime", &["-p"])
+}
+
+pub fn uname() -> Option<String> {
+    output("uname", &["-smr"])
+}
+
+/// The 1, 5 and 15 minute load averages.
+pub fn load() -> Option<String> {
+    let loadavg = fs::read_to_string("/proc/loadavg").ok()?;
+    Some(loadavg.split_whitespace().take(3).collect::<Vec<_>>().join(" "))
+}
+
+pub fn memory() -> Option<String> {
+    let (used, total) = meminfo()?;
+    Some(format!("{}/{}M", used / 1024, total / 1024))
+}
+
+pub fn memory_used() -> Option<f32> {
+    let (used, total) = meminfo()?;
+    Some(used as f32 / total as f32)
+}
+
+/// The filesystem `path` is on.
+pub fn disk(path: &str) -> Option<String> {
+    let (used, total) = statvfs(path)?;
+    let gigs = |bytes| bytes as f64 / (1 << 30) as f64;
+    Some(format!("{:.1}/{:.1}G", gigs(used), gigs(total)))
+}
+
+pub fn disk_used(path: &str) -> Option<f32> {
+    let (used, total) = statvfs(path)?;
+    Some(used as f32 / total as f32)
+}
+
+/// Every address but loopback, one a line.
+pub fn ip() -> Option<String> {
+    let addresses = output("hostname", &["-I"])?;
+    Some(addresses.split_whitespace().collect::<Vec<_>>().join("\n"))
+}
+
+/// The local time, `format` as strftime has it.
+pub fn clock(format: &str) -> Option<String> {
+    let format = CString::new(format).ok()?;
+    let mut buf = [0u8; 128];
+    let n = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm = MaybeUninit::<libc::tm>::uninit();
+        if libc::localtime_r(&now, tm.as_mut_ptr()).is_null() {
+            return None;
+        }
+        libc::strftime(
+            buf.as_mut_ptr() as *mut libc::c_char,
+            buf.len(),
+            format.as_ptr(),
+            tm.as_ptr(),
+        )
+    };
+    String::from_utf8(buf[..n].to_vec()).ok()
+}
+
+/// What `run` prints, run by the shell.
+pub fn command(run: &str) -> Option<String> {
+    output("sh", &["-c", run])
+}
+
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+}
+
+// used and total kB, what's used being what isn't available, like free's
+fn meminfo() -> Option<(u64, u64)> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|kb| kb.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+    };
+    let total = field("MemTotal:")?;
+    Some((total.saturating_sub(field("MemAvailable:")?), total))
+}
+
+// used and total bytes, like df's
+#[allow(clippy::unnecessary_cast)] // they're 32 bit on a 32 bit Pi OS
+fn statvfs(path: &str) -> Option<(u64, u64)> {
+    let path = CString::new(path).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+    let block = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block;
+    Some((total - stat.f_bfree as u64 * block, total))
+}
@@ -0,0 +1,211 @@
+// Which SPI device and GPIO lines the panel's on. A profile for each
+// board we've had one on, changed line by line from a config file and
+// then the command line, so a new wiring doesn't need a rebuild.
+//
+// The config file is `key = value` lines, `#` starts a comment:
+//
+//     profile = waveshare-2in13
+//     spi = /dev/spidev0.1
+//     busy = 5
+//
+// Keys are the field names below, and `profile`, which starts over from
+// that profile's wiring. They apply in order.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wiring {
+    /// the spidev device
+    pub spi: String,
+    pub spi_hz: u32,
+    /// SPI mode, 0 to 3
+    pub spi_mode: u8,
+    /// the GPIO character device the lines below are on
+    pub gpiochip: String,
+    // BCM numbers, which are the line offsets on a Pi's gpiochip0
+    pub busy: u32,
+    pub dc: u32,
+    pub reset: u32,
+    /// chip select, when the panel's is on a GPIO line rather than CE0 or
+    /// CE1, which spidev drives itself. `none` in a config file.
+    pub cs: Option<u32>,
+}
+
+/// Names for `--profile` and `profile =`.
+pub const PROFILES: [&str; 3] = ["inky-phat", "waveshare-2in13", "adafruit-bonnet"];
+
+impl Wiring {
+    pub fn profile(name: &str) -> Option<Wiring> {
+        let spi = String::from("/dev/spidev0.0");
+        let gpiochip = "/dev/gpiochip0";
+        match name {
+            // https://pinout.xyz/pinout/inky_phat
+            "inky-phat" => Some(Wiring {
+                spi,
+                spi_hz: 4_000_000,
+                spi_mode: 0,
+                gpiochip: String::from(gpiochip),
+                busy: 17,
+                dc: 22,
+                reset: 27,
+                cs: None,
+            }),
+            // Waveshare's 2.13" e-Paper HAT
+            "waveshare-2in13" => Some(Wiring {
+                spi,
+                spi_hz: 4_000_000,
+                spi_mode: 0,
+                gpiochip: String::from(gpiochip),
+                busy: 24,
+                dc: 25,
+                reset: 17,
+                cs: None,
+            }),
+            // Adafruit's 2.13" e-ink bonnet, the SRAM on CE1 goes unused
+            "adafruit-bonnet" => Some(Wiring {
+                spi,
+                spi_hz: 4_000_000,
+                spi_mode: 0,
+                gpiochip: String::from(gpiochip),
+                busy: 17,
+                dc: 22,
+                reset: 27,
+                cs: None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Change one thing, by its config file key.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let line = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("{} wants a BCM line number, not {}", key, value))
+        };
+        match key {
+            "profile" => {
+                *self = Wiring::profile(value).ok_or_else(|| {
+                    format!("no profile {}, try one of {}", value, PROFILES.join(", "))
+                })?
+            }
+            "spi" => self.spi = String::from(value),
+            "gpiochip" => self.gpiochip = String::from(value),
+            "spi_hz" => {
+                self.spi_hz = value
+                    .parse()
+                    .map_err(|_| format!("spi_hz wants a clock in Hz, not {}", value))?
+            }
+            "spi_mode" => {
+                self.spi_mode = value
+                    .parse()
+                    .ok()
+                    .filter(|&mode| mode <= 3)
+                    .ok_or_else(|| format!("spi_mode wants 0 to 3, not {}", value))?
+            }
+            "busy" => self.busy = line(value)?,
+            "dc" => self.dc = line(value)?,
+            "reset" => self.reset = line(value)?,
+            "cs" if value == "none" => self.cs = None,
+            "cs" => self.cs = Some(line(value)?),
+            _ => return Err(format!("no such setting {}", key)),
+        }
+        Ok(())
+    }
+
+    /// Apply a config file's settings on top.
+    pub fn load(&mut self, path: &Path) -> io::Result<()> {
+        let text = fs::read_to_string(path)?;
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line.split_once('=').unwrap_or((line, ""));
+            self.set(key.trim(), value.trim()).map_err(|e| {
+                let at = format!("{}:{}: {}", path.display(), n + 1, e);
+                io::Error::new(io::ErrorKind::InvalidData, at)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Wiring {
+    fn default() -> Self {
+        Wiring::profile(PROFILES[0]).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // through a file, named for the test so they don't trip over each other
+    fn load(name: &str, text: &str) -> io::Result<Wiring> {
+        let path = std::env::temp_dir().join(format!("e_ink_hello-{}.conf", name));
+        fs::write(&path, text)?;
+        let mut wiring = Wiring::default();
+        let loaded = wiring.load(&path).map(|_| wiring);
+        fs::remove_file(&path).ok();
+        loaded
+    }
+
+    #[test]
+    fn unknown_keys_are_errors() {
+        let mut wiring = Wiring::default();
+        assert_eq!(
+            wiring.set("colour", "red"),
+            Err(String::from("no such setting colour"))
+        );
+        let e = load("unknown", "busy = 5\nspeed = 4\n").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(
+            e.to_string().ends_with(":2: no such setting speed"),
+            "{}",
+            e
+        );
+    }
+
+    #[test]
+    fn pins_are_line_numbers() {
+        let mut wiring = Wiring::default();
+        for (key, value) in [
+            ("busy", "GPIO17"),
+            ("dc", "-1"),
+            ("reset", ""),
+            ("cs", "2.5"),
+        ] {
+            assert!(wiring.set(key, value).is_err(), "{} = {}", key, value);
+        }
+        assert!(wiring.set("spi_mode", "4").is_err());
+        assert!(wiring.set("profile", "inky-what").is_err());
+        // and none of that changed anything
+        assert_eq!(wiring, Wiring::default());
+        wiring.set("busy", "5").unwrap();
+        assert_eq!(wiring.busy, 5);
+        // chip select's spidev's unless there's a line for it
+        assert_eq!(wiring.cs, None);
+        wiring.set("cs", "8").unwrap();
+        assert_eq!(wiring.cs, Some(8));
+        wiring.set("cs", "none").unwrap();
+        assert_eq!(wiring.cs, None);
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        let text = "# the HAT, moved over\n\
+                    \n\
+                    profile = waveshare-2in13\n   \n\
+                    busy = 5  # off 24\n\
+                    #dc = 6\n";
+        let mut expected = Wiring::profile("waveshare-2in13").unwrap();
+        expected.busy = 5;
+        assert_eq!(load("comments", text).unwrap(), expected);
+        // line numbers still count them
+        let e = load("comments-bad", "# pins\n\nbusy = five\n").unwrap_err();
+        assert!(e.to_string().contains(":3: busy wants"), "{}", e);
+    }
+}
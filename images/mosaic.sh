@@ -0,0 +1,16 @@
+# Tile the first 16 128x64 pictures 4 by 4 into one 512x256 sheet for
+# window mode, 1bpp rows like the .gray files
+mkdir -p 512x256
+OUT=512x256/001.gray
+: > $OUT
+for TY in 0 1 2 3
+do
+  for Y in `seq 0 63`
+  do
+    for TX in 0 1 2 3
+    do
+      N=`printf %03d $[$TY * 4 + $TX + 1]`
+      dd if=128x64/$N.gray bs=16 skip=$Y count=1 status=none >> $OUT
+    done
+  done
+done
@@ -0,0 +1,225 @@
+//! Panel brightness: discrete levels, smooth fades, and optionally
+//! following a light sensor.
+//!
+//! The light sensor is an LDR or phototransistor read by the board's ADC,
+//! brighter rooms reading higher. The [`Curve`] maps a reading to a
+//! contrast, and the host can change it, see `Command::Curve`. The board
+//! keeps it across resets.
+
+/// Points on a [`Curve`].
+pub const POINTS: usize = 4;
+
+/// The levels `brightness up` and `brightness down` step between.
+pub const LEVELS: [u8; 5] = [0x01, 0x10, 0x40, 0x90, 0xff];
+
+/// ADC counts the smoothed reading has to move before the panel follows,
+/// so a flickering lamp doesn't make it pump.
+pub const HYSTERESIS: u16 = 64;
+
+/// Contrast steps per fade tick.
+pub const FADE_STEP: u8 = 4;
+
+/// Light reading to contrast, linear between the points and flat past
+/// the ends.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Curve {
+    /// (ADC reading, contrast), readings ascending.
+    pub points: [(u16, u8); POINTS],
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Curve {
+            points: [(0, 0x01), (300, 0x10), (1500, 0x80), (3500, 0xff)],
+        }
+    }
+}
+
+impl Curve {
+    /// Readings have to go up, or the curve is meaningless.
+    pub fn is_valid(&self) -> bool {
+        self.points.windows(2).all(|w| w[0].0 < w[1].0)
+    }
+
+    pub fn contrast(&self, reading: u16) -> u8 {
+        let first = self.points[0];
+        let last = self.points[POINTS - 1];
+        if reading <= first.0 {
+            return first.1;
+        }
+        for w in self.points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+            if reading <= x1 {
+                let t = (reading - x0) as i32;
+                let span = (x1 - x0) as i32;
+                let rise = y1 as i32 - y0 as i32;
+                return (y0 as i32 + rise * t / span) as u8;
+            }
+        }
+        last.1
+    }
+
+    /// One word per point, for the board to store.
+    pub fn to_words(&self) -> [u32; POINTS] {
+        let mut words = [0; POINTS];
+        for (w, &(reading, contrast)) in words.iter_mut().zip(self.points.iter()) {
+            *w = (reading as u32) << 8 | contrast as u32;
+        }
+        words
+    }
+
+    pub fn from_words(words: &[u32; POINTS]) -> Option<Curve> {
+        let mut curve = Curve::default();
+        for (p, &w) in curve.points.iter_mut().zip(words.iter()) {
+            *p = ((w >> 8) as u16, w as u8);
+        }
+        if curve.is_valid() {
+            Some(curve)
+        } else {
+            None
+        }
+    }
+}
+
+/// The next level up or down from `level`.
+pub fn step(level: u8, up: bool) -> u8 {
+    if up {
+        LEVELS.iter().copied().find(|&l| l > level).unwrap_or(level)
+    } else {
+        LEVELS.iter().rev().copied().find(|&l| l < level).unwrap_or(level)
+    }
+}
+
+/// Smooths raw ADC readings and only moves past the hysteresis band.
+pub struct Ambient {
+    // 1/16ths of a count
+    filtered: u32,
+    level: u16,
+    started: bool,
+}
+
+impl Ambient {
+    pub const fn new() -> Self {
+        Ambient {
+            filtered: 0,
+            level: 0,
+            started: false,
+        }
+    }
+
+    /// Feed a raw reading, returns the settled level.
+    pub fn sense(&mut self, raw: u16) -> u16 {
+        if !self.started {
+            self.started = true;
+            self.filtered = (raw as u32) << 4;
+            self.level = raw;
+        }
+        // exponential moving average, 1/16 of the new reading
+        self.filtered = self.filtered - (self.filtered >> 4) + raw as u32;
+        let smooth = (self.filtered >> 4) as u16;
+        if smooth.abs_diff(self.level) > HYSTERESIS {
+            self.level = smooth;
+        }
+        self.level
+    }
+
+    pub fn level(&self) -> u16 {
+        self.level
+    }
+}
+
+impl Default for Ambient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Walks the contrast towards a target a little each tick.
+pub struct Fader {
+    current: u8,
+}
+
+impl Fader {
+    pub const fn new(current: u8) -> Self {
+        Fader { current }
+    }
+
+    pub fn current(&self) -> u8 {
+        self.current
+    }
+
+    /// One step towards `target`. Returns the new contrast if it moved.
+    pub fn tick(&mut self, target: u8) -> Option<u8> {
+        let next = if target > self.current {
+            self.current.saturating_add(FADE_STEP).min(target)
+        } else {
+            self.current.saturating_sub(FADE_STEP).max(target)
+        };
+        if next == self.current {
+            return None;
+        }
+        self.current = next;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_interpolates_and_clamps() {
+        let curve = Curve::default();
+        assert_eq!(curve.contrast(0), 0x01);
+        assert_eq!(curve.contrast(300), 0x10);
+        assert_eq!(curve.contrast(900), 0x48);
+        assert_eq!(curve.contrast(4095), 0xff);
+    }
+
+    #[test]
+    fn curve_survives_storage() {
+        let curve = Curve {
+            points: [(10, 0xff), (20, 0x80), (4000, 0x02), (4095, 0x01)],
+        };
+        assert_eq!(Curve::from_words(&curve.to_words()), Some(curve));
+        // erased or garbage storage isn't a curve
+        assert_eq!(Curve::from_words(&[0xffff_ffff; POINTS]), None);
+    }
+
+    #[test]
+    fn levels_step() {
+        assert_eq!(step(0x10, true), 0x40);
+        assert_eq!(step(0x30, true), 0x40);
+        assert_eq!(step(0x30, false), 0x10);
+        assert_eq!(step(0xff, true), 0xff);
+        assert_eq!(step(0x01, false), 0x01);
+    }
+
+    #[test]
+    fn ambient_ignores_small_wobbles() {
+        let mut ambient = Ambient::new();
+        assert_eq!(ambient.sense(1000), 1000);
+        for i in 0..200 {
+            let wobble = if i % 2 == 0 { 1040 } else { 960 };
+            assert_eq!(ambient.sense(wobble), 1000);
+        }
+        // the lights go off
+        for _ in 0..200 {
+            ambient.sense(100);
+        }
+        assert!(ambient.level() < 100 + HYSTERESIS);
+    }
+
+    #[test]
+    fn fader_walks_to_the_target() {
+        let mut fader = Fader::new(0xff);
+        let mut steps = 0;
+        while fader.tick(0x10).is_some() {
+            steps += 1;
+        }
+        assert_eq!(fader.current(), 0x10);
+        // 0xef down, 4 at a time
+        assert_eq!(steps, 60);
+        assert_eq!(fader.tick(0x10), None);
+    }
+}
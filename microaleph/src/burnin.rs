@@ -0,0 +1,280 @@
+//! Keeping static pictures from burning into the OLED.
+//!
+//! The [`Guard`] sits in the draw path and never touches the pictures
+//! themselves. It nudges where the picture goes, occasionally shows it
+//! inverted, dims the panel when nothing has changed for a while, and
+//! once a day runs an exercise pattern that lights every pixel evenly.
+//!
+//! The board calls [`Guard::second`] once a second and redraws when it
+//! returns true, and calls [`Guard::activity`] when a new picture goes up
+//! or the host sends a command.
+
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+};
+
+/// Every knob, a zero period turns that trick off.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Config {
+    /// How far the picture wanders from centre, at most 2 pixels.
+    pub orbit_px: u8,
+    /// Seconds between orbit steps.
+    pub orbit_s: u32,
+    /// Seconds between inverted spells.
+    pub invert_every_s: u32,
+    /// How long each inverted spell lasts.
+    pub invert_s: u32,
+    /// Seconds without a new picture or command before dimming.
+    pub idle_s: u32,
+    /// Seconds to ramp down to `dim`.
+    pub ramp_s: u32,
+    /// Contrast once fully dimmed.
+    pub dim: u8,
+    /// Seconds between exercise runs, a day by default. There's no real
+    /// time clock, so "nightly" counts from power up.
+    pub exercise_every_s: u32,
+    /// How long each exercise run lasts.
+    pub exercise_s: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            orbit_px: 1,
+            orbit_s: 60,
+            invert_every_s: 3600,
+            invert_s: 10,
+            idle_s: 30 * 60,
+            ramp_s: 60,
+            dim: 0x10,
+            exercise_every_s: 24 * 3600,
+            exercise_s: 60,
+        }
+    }
+}
+
+// centre, then once around the square
+const ORBIT: [(i32, i32); 9] = [
+    (0, 0),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+pub struct Guard {
+    config: Config,
+    uptime_s: u32,
+    idle_s: u32,
+}
+
+/// Whether `now` falls in the last `length` seconds of each `every`.
+fn in_window(now: u32, every: u32, length: u32) -> bool {
+    every > 0 && now % every >= every.saturating_sub(length)
+}
+
+impl Guard {
+    pub fn new(config: Config) -> Self {
+        Guard {
+            config,
+            uptime_s: 0,
+            idle_s: 0,
+        }
+    }
+
+    /// A second went by. Returns whether the panel needs a redraw.
+    pub fn second(&mut self) -> bool {
+        let before = (self.offset(), self.inverted(), self.brightness(0xff));
+        let exercising = self.exercising();
+        self.uptime_s = self.uptime_s.wrapping_add(1);
+        self.idle_s = self.idle_s.saturating_add(1);
+        // the exercise pattern moves every second, and needs one last
+        // redraw to put the picture back
+        exercising
+            || self.exercising()
+            || before != (self.offset(), self.inverted(), self.brightness(0xff))
+    }
+
+    /// Something changed on screen, so it isn't idle.
+    pub fn activity(&mut self) {
+        self.idle_s = 0;
+    }
+
+    /// Where to put the picture's top left corner.
+    pub fn offset(&self) -> Point {
+        let px = self.config.orbit_px.min(2) as i32;
+        if px == 0 || self.config.orbit_s == 0 {
+            return Point::zero();
+        }
+        let step = (self.uptime_s / self.config.orbit_s) as usize;
+        let (x, y) = ORBIT[step % ORBIT.len()];
+        // with 2 pixels, go round once at 1 and once at 2
+        let r = 1 + (step / ORBIT.len()) as i32 % px;
+        Point::new(x * r, y * r)
+    }
+
+    pub fn inverted(&self) -> bool {
+        in_window(self.uptime_s, self.config.invert_every_s, self.config.invert_s)
+    }
+
+    pub fn exercising(&self) -> bool {
+        in_window(
+            self.uptime_s,
+            self.config.exercise_every_s,
+            self.config.exercise_s,
+        )
+    }
+
+    /// The contrast to use instead of `level`, ramped down when idle.
+    pub fn brightness(&self, level: u8) -> u8 {
+        let c = &self.config;
+        if c.idle_s == 0 || self.idle_s < c.idle_s || level <= c.dim {
+            return level;
+        }
+        let into = self.idle_s - c.idle_s;
+        if into >= c.ramp_s {
+            return c.dim;
+        }
+        let drop = (level - c.dim) as u32 * into / c.ramp_s;
+        level - drop as u8
+    }
+
+    /// A checkerboard that flips every second, so every pixel spends
+    /// the run half on.
+    pub fn draw_exercise<D>(&self, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let area: Rectangle = display.bounding_box();
+        let phase = self.uptime_s as i32;
+        let colors = area.points().map(|p| {
+            if (p.x + p.y + phase) % 2 == 0 {
+                BinaryColor::On
+            } else {
+                BinaryColor::Off
+            }
+        });
+        display.fill_contiguous(&area, colors)
+    }
+}
+
+/// Draws everything through it with the colours swapped.
+pub struct Inverted<'a, D>(pub &'a mut D);
+
+impl<D: DrawTarget<Color = BinaryColor>> OriginDimensions for Inverted<'_, D> {
+    fn size(&self) -> Size {
+        self.0.bounding_box().size
+    }
+}
+
+impl<D: DrawTarget<Color = BinaryColor>> DrawTarget for Inverted<'_, D> {
+    type Color = BinaryColor;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<BinaryColor>>,
+    {
+        self.0
+            .draw_iter(pixels.into_iter().map(|Pixel(p, c)| Pixel(p, c.invert())))
+    }
+
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Self::Error> {
+        self.0.clear(color.invert())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(guard: &mut Guard, seconds: u32) {
+        for _ in 0..seconds {
+            guard.second();
+        }
+    }
+
+    #[test]
+    fn orbit_stays_close() {
+        let mut guard = Guard::new(Config {
+            orbit_px: 2,
+            orbit_s: 1,
+            ..Config::default()
+        });
+        let mut furthest = 0;
+        for _ in 0..100 {
+            let p = guard.offset();
+            assert!(p.x.abs() <= 2 && p.y.abs() <= 2);
+            furthest = furthest.max(p.x.abs());
+            guard.second();
+        }
+        assert_eq!(furthest, 2);
+    }
+
+    #[test]
+    fn dims_when_idle() {
+        let mut guard = Guard::new(Config {
+            idle_s: 10,
+            ramp_s: 10,
+            dim: 0x10,
+            ..Config::default()
+        });
+        run(&mut guard, 10);
+        assert_eq!(guard.brightness(0xff), 0xff);
+        run(&mut guard, 5);
+        let half = guard.brightness(0xff);
+        assert!(half < 0xff && half > 0x10);
+        run(&mut guard, 5);
+        assert_eq!(guard.brightness(0xff), 0x10);
+        // already dimmer than that, leave it be
+        assert_eq!(guard.brightness(0x08), 0x08);
+
+        guard.activity();
+        assert_eq!(guard.brightness(0xff), 0xff);
+    }
+
+    #[test]
+    fn inverts_and_exercises_on_schedule() {
+        let mut guard = Guard::new(Config {
+            invert_every_s: 10,
+            invert_s: 2,
+            exercise_every_s: 100,
+            exercise_s: 5,
+            ..Config::default()
+        });
+        run(&mut guard, 8);
+        assert!(guard.inverted());
+        run(&mut guard, 2);
+        assert!(!guard.inverted());
+
+        run(&mut guard, 84);
+        assert!(!guard.exercising());
+        // the last second before a run asks to redraw into the pattern
+        assert!(guard.second());
+        assert!(guard.exercising());
+        run(&mut guard, 5);
+        assert!(!guard.exercising());
+    }
+
+    #[test]
+    fn zero_turns_things_off() {
+        let mut guard = Guard::new(Config {
+            orbit_px: 0,
+            invert_every_s: 0,
+            idle_s: 0,
+            exercise_every_s: 0,
+            ..Config::default()
+        });
+        for _ in 0..5000 {
+            assert!(!guard.second());
+        }
+        assert_eq!(guard.offset(), Point::zero());
+        assert_eq!(guard.brightness(0x80), 0x80);
+    }
+}
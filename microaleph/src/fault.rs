@@ -0,0 +1,165 @@
+//! Render panics and hard faults onto the display before halting, so a
+//! wedged board can be diagnosed from a photo of its screen.
+//!
+//! The board crates own the `#[panic_handler]` and the `HardFault`
+//! exception. They rebuild a display from stolen peripherals and pass it
+//! to [`show`] together with the lines built here.
+
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_5X8, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Baseline, Text},
+};
+use heapless::{String, Vec};
+
+/// Characters per line with FONT_5X8 on a 128 pixel wide panel.
+pub const COLS: usize = 25;
+/// Lines with FONT_5X8 on a 64 pixel tall panel.
+pub const ROWS: usize = 8;
+
+pub type Line = String<COLS>;
+pub type Lines = Vec<Line, ROWS>;
+
+/// The registers worth photographing after a hard fault.
+pub struct FaultRegs {
+    pub pc: u32,
+    pub lr: u32,
+    /// Configurable Fault Status Register, SCB offset 0x28.
+    pub cfsr: u32,
+}
+
+/// Wraps formatted text into display lines, dropping whatever doesn't fit.
+struct LineWriter {
+    lines: Lines,
+}
+
+impl LineWriter {
+    fn new() -> Self {
+        let mut lines = Vec::new();
+        lines.push(String::new()).ok();
+        LineWriter { lines }
+    }
+
+    fn newline(&mut self) {
+        self.lines.push(String::new()).ok();
+    }
+}
+
+impl Write for LineWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if c == '\n' {
+                self.newline();
+                continue;
+            }
+            // non ascii won't render in FONT_5X8 anyway
+            let c = if c.is_ascii() { c } else { '?' };
+            let full = self.lines.last().is_none_or(|l| l.len() == COLS);
+            if full {
+                if self.lines.is_full() {
+                    return Ok(());
+                }
+                self.newline();
+            }
+            if let Some(line) = self.lines.last_mut() {
+                line.push(c).ok();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lines for a panic: the message and file:line, wrapped to the panel.
+pub fn panic_lines(info: &PanicInfo) -> Lines {
+    lines(format_args!("PANIC\n{}", info))
+}
+
+/// Lines for a hard fault: PC, LR and CFSR in hex.
+pub fn hardfault_lines(regs: &FaultRegs) -> Lines {
+    lines(format_args!(
+        "HARDFAULT\nPC   {:#010x}\nLR   {:#010x}\nCFSR {:#010x}",
+        regs.pc, regs.lr, regs.cfsr
+    ))
+}
+
+fn lines(args: fmt::Arguments) -> Lines {
+    let mut w = LineWriter::new();
+    w.write_fmt(args).ok();
+    w.lines
+}
+
+/// Clear the target and draw the lines top to bottom. Drawing errors are
+/// ignored, there is nothing left to report them to.
+pub fn show<D>(display: &mut D, lines: &Lines)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    display.clear(BinaryColor::Off).ok();
+    let style = MonoTextStyle::new(&FONT_5X8, BinaryColor::On);
+    for (i, line) in lines.iter().enumerate() {
+        let y = i as i32 * FONT_5X8.character_size.height as i32;
+        Text::with_baseline(line, Point::new(0, y), style, Baseline::Top)
+            .draw(display)
+            .ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lines_wrap_at_cols() {
+        let mut w = LineWriter::new();
+        write!(w, "{}", "x".repeat(COLS + 3)).unwrap();
+        assert_eq!(w.lines.len(), 2);
+        assert_eq!(w.lines[0].len(), COLS);
+        assert_eq!(w.lines[1], "xxx");
+        // a line that's exactly full doesn't leave an empty one behind
+        let mut w = LineWriter::new();
+        write!(w, "{}", "x".repeat(COLS)).unwrap();
+        assert_eq!(w.lines.len(), 1);
+    }
+
+    #[test]
+    fn non_ascii_shows_as_question_marks() {
+        let mut w = LineWriter::new();
+        write!(w, "25°C").unwrap();
+        assert_eq!(w.lines[0], "25?C");
+    }
+
+    // PanicInfo can't be made outside a panic handler, so this goes
+    // through what panic_lines formats with
+    #[test]
+    fn panics_are_cut_off_at_rows() {
+        let message = "a very long panic message ".repeat(10);
+        let lines = lines(format_args!("PANIC\n{}", message));
+        assert_eq!(lines.len(), ROWS);
+        assert_eq!(lines[0], "PANIC");
+        assert_eq!(lines[1], "a very long panic message");
+        assert!(lines.iter().all(|l| l.len() <= COLS));
+    }
+
+    #[test]
+    fn hard_faults_fit_the_screen() {
+        let regs = FaultRegs {
+            pc: 0x0800_1234,
+            lr: 0xffff_fff9,
+            cfsr: 0x0000_8200,
+        };
+        let lines = hardfault_lines(&regs);
+        assert_eq!(
+            &lines[..],
+            &[
+                "HARDFAULT",
+                "PC   0x08001234",
+                "LR   0xfffffff9",
+                "CFSR 0x00008200"
+            ]
+        );
+    }
+}
@@ -0,0 +1,520 @@
+//! A 128 wide framebuffer in the SSD1306's own memory layout, and the I2C
+//! transfers that bring a panel up to date with it.
+//!
+//! Pages of 128 columns, eight for 64 rows and four for 32, each column
+//! byte holding eight rows with the top one in bit 0, so the panel takes
+//! it as is. Every controller in [`oled`](crate::oled) shares the layout.
+//!
+//! Redrawing a picture usually changes a few pages at most, so rather than
+//! the whole 1K, [`Transfers::plan`] compares the new frame with the one
+//! the panel is showing and only sends the column runs that changed, each
+//! one a window set up with the panel's address commands. Boards
+//! just write each transfer as it comes, which is what lets one hand them
+//! to DMA.
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use heapless::Vec;
+
+use crate::oled::{Panel, ADDRESS_COMMANDS};
+
+pub const WIDTH: usize = 128;
+/// The tallest panel, shorter ones use the top of the buffer.
+pub const HEIGHT: usize = 64;
+pub const PAGES: usize = HEIGHT / 8;
+/// Pixel bytes.
+pub const BYTES: usize = WIDTH * PAGES;
+
+/// Most windows in a page before they get merged into one.
+pub const RUNS: usize = 4;
+
+/// Unchanged columns worth sending anyway to save addressing another
+/// window, which costs about as much again in commands and I2C overhead.
+pub const GAP: usize = 10;
+
+// Co = 0, D/C# = 0: everything after this is commands
+const COMMAND: u8 = 0x00;
+// Co = 0, D/C# = 1: everything after this is display data
+const DATA: u8 = 0x40;
+const ADDRESS_LEN: usize = ADDRESS_COMMANDS + 1;
+
+#[derive(Clone)]
+pub struct Frame {
+    buf: [u8; BYTES],
+    pages: usize,
+}
+
+impl Frame {
+    /// A 128x64 frame.
+    pub fn new() -> Self {
+        Self::with_height(HEIGHT)
+    }
+
+    /// `height` rows, a multiple of 8 up to [`HEIGHT`].
+    pub fn with_height(height: usize) -> Self {
+        Frame {
+            buf: [0; BYTES],
+            pages: height.min(HEIGHT) / 8,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.pages * 8
+    }
+
+    /// The pixel bytes, page by page.
+    pub fn pixels(&self) -> &[u8] {
+        &self.buf[..self.pages * WIDTH]
+    }
+
+    /// The pixel bytes under a window.
+    pub fn window(&self, w: &Window) -> &[u8] {
+        &self.buf[w.page * WIDTH + w.start..w.page * WIDTH + w.end]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, on: bool) {
+        if x >= WIDTH || y >= self.height() {
+            return;
+        }
+        let byte = &mut self.buf[(y / 8) * WIDTH + x];
+        let bit = 1 << (y % 8);
+        if on {
+            *byte |= bit;
+        } else {
+            *byte &= !bit;
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < WIDTH && y < self.height() && self.buf[(y / 8) * WIDTH + x] & (1 << (y % 8)) != 0
+    }
+
+    /// Where this frame differs from `shown` in one page, as few windows
+    /// as are worth it.
+    pub fn changes(&self, shown: &Frame, page: usize) -> Vec<Window, RUNS> {
+        let row = page * WIDTH..(page + 1) * WIDTH;
+        let (new, old) = (&self.buf[row.clone()], &shown.buf[row]);
+        let changed = |x: usize| new[x] != old[x];
+        let mut windows = Vec::new();
+        let mut x = 0;
+        while x < WIDTH {
+            if !changed(x) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            let mut end = x + 1;
+            x = end;
+            // carry on through short gaps
+            while x < WIDTH && x - end < GAP {
+                if changed(x) {
+                    end = x + 1;
+                }
+                x += 1;
+            }
+            if windows.push(Window { page, start, end }).is_err() {
+                // too bitty, send everything between the first and last change
+                let first = windows[0].start;
+                let last = (0..WIDTH).rev().find(|&x| changed(x)).unwrap();
+                windows.clear();
+                windows.push(Window { page, start: first, end: last + 1 }).ok();
+                break;
+            }
+            x = end;
+        }
+        windows
+    }
+
+    /// Every window that needs sending to a panel showing `shown`, or the
+    /// whole screen if nobody knows what it's showing.
+    pub fn windows<'a>(&'a self, shown: Option<&'a Frame>) -> impl Iterator<Item = Window> + 'a {
+        (0..self.pages).flat_map(move |page| match shown {
+            Some(shown) => self.changes(shown, page),
+            None => Vec::from_slice(&[Window::page(page)]).unwrap(),
+        })
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OriginDimensions for Frame {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, self.height() as u32)
+    }
+}
+
+impl DrawTarget for Frame {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<BinaryColor>>,
+    {
+        for Pixel(p, c) in pixels {
+            if p.x >= 0 && p.y >= 0 {
+                self.set(p.x as usize, p.y as usize, c.is_on());
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Self::Error> {
+        let fill = if color.is_on() { 0xff } else { 0x00 };
+        for b in self.buf.iter_mut() {
+            *b = fill;
+        }
+        Ok(())
+    }
+}
+
+/// Columns `start..end` of one page.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Window {
+    pub page: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Window {
+    pub fn page(page: usize) -> Self {
+        Window {
+            page,
+            start: 0,
+            end: WIDTH,
+        }
+    }
+}
+
+// every window's address and data, at worst
+const MOST: usize = PAGES * (WIDTH + RUNS * (ADDRESS_LEN + 1));
+
+/// The I2C writes that update a panel, back to back.
+pub struct Transfers {
+    bytes: Vec<u8, MOST>,
+    ends: Vec<usize, { PAGES * RUNS * 2 }>,
+}
+
+impl Transfers {
+    pub const fn new() -> Self {
+        Transfers {
+            bytes: Vec::new(),
+            ends: Vec::new(),
+        }
+    }
+
+    /// Plan the writes that bring `panel` showing `shown` up to `frame`.
+    /// With no `shown`, say after an init, that's the whole screen.
+    pub fn plan(&mut self, panel: &Panel, frame: &Frame, shown: Option<&Frame>) {
+        self.bytes.clear();
+        self.ends.clear();
+        for w in frame.windows(shown) {
+            self.push(&[&[COMMAND], &panel.address(&w)]);
+            self.push(&[&[DATA], frame.window(&w)]);
+        }
+    }
+
+    fn push(&mut self, parts: &[&[u8]]) {
+        for part in parts {
+            // MOST covers the worst case
+            self.bytes.extend_from_slice(part).unwrap();
+        }
+        self.ends.push(self.bytes.len()).unwrap();
+    }
+
+    /// How many writes.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// The bytes of write `i`, control byte first.
+    pub fn get(&self, i: usize) -> Option<&[u8]> {
+        let end = *self.ends.get(i)?;
+        let start = if i == 0 { 0 } else { self.ends[i - 1] };
+        Some(&self.bytes[start..end])
+    }
+
+    /// All the bytes, for seeing what a diff saved.
+    pub fn bytes(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl Default for Transfers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oled;
+    use embedded_graphics::{
+        image::{Image, ImageRaw},
+        primitives::{Circle, PrimitiveStyle, Rectangle},
+    };
+
+    // What the controller does with the writes: horizontal addressing on
+    // the SSD130x, page addressing on the SH1106's wider RAM
+    struct Gddram {
+        ram: [u8; RAM_WIDTH * PAGES],
+        columns: (usize, usize),
+        pages: (usize, usize),
+        x: usize,
+        page: usize,
+        horizontal: bool,
+    }
+
+    const RAM_WIDTH: usize = WIDTH + 4;
+
+    impl Gddram {
+        fn new() -> Self {
+            Gddram {
+                ram: [0x5a; RAM_WIDTH * PAGES],
+                columns: (0, WIDTH - 1),
+                pages: (0, PAGES - 1),
+                x: 0,
+                page: 0,
+                horizontal: true,
+            }
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            match bytes[0] {
+                COMMAND => {
+                    let mut commands = bytes[1..].iter().map(|&b| b as usize);
+                    while let Some(command) = commands.next() {
+                        let mut arg = || commands.next().unwrap();
+                        match command as u8 {
+                            oled::COLUMN_ADDRESS => {
+                                self.columns = (arg(), arg());
+                                self.x = self.columns.0;
+                            }
+                            oled::PAGE_ADDRESS => {
+                                self.pages = (arg(), arg());
+                                self.page = self.pages.0;
+                            }
+                            c @ 0xb0..=0xb7 => {
+                                self.horizontal = false;
+                                self.page = (c & 0x07) as usize;
+                            }
+                            c @ 0x00..=0x0f => self.x = (self.x & 0xf0) | c as usize,
+                            c @ 0x10..=0x1f => {
+                                self.x = (self.x & 0x0f) | ((c as usize & 0x0f) << 4)
+                            }
+                            other => panic!("unexpected command {:#x}", other),
+                        }
+                    }
+                }
+                DATA => {
+                    for &b in &bytes[1..] {
+                        self.ram[self.page * RAM_WIDTH + self.x] = b;
+                        self.x += 1;
+                        if self.horizontal && self.x > self.columns.1 {
+                            self.x = self.columns.0;
+                            self.page += 1;
+                            if self.page > self.pages.1 {
+                                self.page = self.pages.0;
+                            }
+                        }
+                    }
+                }
+                other => panic!("unexpected control byte {:#x}", other),
+            }
+        }
+
+        fn run(&mut self, transfers: &Transfers) {
+            for i in 0..transfers.len() {
+                self.write(transfers.get(i).unwrap());
+            }
+        }
+
+        // the columns on the glass, page by page
+        fn glass(&self, offset: usize, pages: usize) -> Vec<u8, BYTES> {
+            (0..pages)
+                .flat_map(|p| &self.ram[p * RAM_WIDTH + offset..p * RAM_WIDTH + offset + WIDTH])
+                .copied()
+                .collect()
+        }
+    }
+
+    const SSD1306: Panel = Panel::SSD1306_128X64;
+
+    // xorshift, so the noise is the same every run
+    fn noise(seed: &mut u32) -> u32 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 17;
+        *seed ^= *seed << 5;
+        *seed
+    }
+
+    #[test]
+    fn pixels_land_in_pages() {
+        let mut frame = Frame::new();
+        frame.set(0, 0, true);
+        frame.set(5, 9, true);
+        frame.set(127, 63, true);
+        assert_eq!(frame.pixels()[0], 0x01);
+        assert_eq!(frame.pixels()[WIDTH + 5], 0x02);
+        assert_eq!(frame.pixels()[BYTES - 1], 0x80);
+    }
+
+    #[test]
+    fn gray_files_draw_the_right_way_up() {
+        // .gray files are rows, MSB first
+        let mut gray = [0u8; BYTES];
+        gray[0] = 0x80;
+        gray[16 * 10 + 1] = 0x01;
+        let raw: ImageRaw<BinaryColor> = ImageRaw::new(&gray, WIDTH as u32);
+        let mut frame = Frame::new();
+        Image::new(&raw, Point::zero()).draw(&mut frame).unwrap();
+        assert!(frame.get(0, 0));
+        assert!(frame.get(15, 10));
+        assert!(!frame.get(1, 0));
+    }
+
+    #[test]
+    fn changes_bridge_short_gaps() {
+        let (mut frame, shown) = (Frame::new(), Frame::new());
+        for &x in &[3, 3 + GAP, 40, 100] {
+            frame.set(x, 20, true);
+        }
+        assert_eq!(frame.changes(&shown, 0).len(), 0);
+        let windows = frame.changes(&shown, 2);
+        assert_eq!(
+            &windows[..],
+            &[
+                Window { page: 2, start: 3, end: 4 + GAP },
+                Window { page: 2, start: 40, end: 41 },
+                Window { page: 2, start: 100, end: 101 },
+            ]
+        );
+        // one pixel in every 16 columns is too bitty to send apiece
+        for x in (0..WIDTH).step_by(16) {
+            frame.set(x, 60, true);
+        }
+        assert_eq!(
+            &frame.changes(&shown, 7)[..],
+            &[Window { page: 7, start: 0, end: 113 }]
+        );
+    }
+
+    #[test]
+    fn nothing_changed_nothing_sent() {
+        let mut frame = Frame::new();
+        Circle::new(Point::new(10, 10), 30)
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut frame)
+            .unwrap();
+        let mut transfers = Transfers::new();
+        transfers.plan(&SSD1306, &frame, Some(&frame.clone()));
+        assert!(transfers.is_empty());
+        transfers.plan(&SSD1306, &frame, None);
+        assert_eq!(transfers.len(), 2 * PAGES);
+
+        // a step to the right only touches the circle's edges
+        let mut moved = Frame::new();
+        Circle::new(Point::new(11, 10), 30)
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut moved)
+            .unwrap();
+        transfers.plan(&SSD1306, &moved, Some(&frame));
+        assert!(transfers.bytes() < BYTES / 8);
+    }
+
+    #[test]
+    fn diffs_match_full_flushes() {
+        let mut full = Gddram::new();
+        let mut diffed = Gddram::new();
+        let mut transfers = Transfers::new();
+        let mut shown: Option<Frame> = None;
+        let (mut full_bytes, mut diffed_bytes) = (0, 0);
+        let mut seed = 0x1234_5678;
+        for step in 0..200 {
+            let mut frame = shown.clone().unwrap_or_default();
+            match step % 4 {
+                // something sliding across
+                0 => {
+                    frame.clear(BinaryColor::Off).unwrap();
+                    Rectangle::new(Point::new(step % 120, step % 50), Size::new(9, 13))
+                        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                        .draw(&mut frame)
+                        .unwrap();
+                }
+                // a few stray pixels
+                1 => {
+                    for _ in 0..noise(&mut seed) % 40 {
+                        let n = noise(&mut seed) as usize;
+                        frame.set(n % WIDTH, (n >> 8) % HEIGHT, n & 0x10000 != 0);
+                    }
+                }
+                // a whole new picture
+                2 => {
+                    for b in frame.buf.iter_mut() {
+                        *b = noise(&mut seed) as u8;
+                    }
+                }
+                // or the same again
+                _ => {}
+            }
+
+            transfers.plan(&SSD1306, &frame, None);
+            full.run(&transfers);
+            full_bytes += transfers.bytes();
+
+            transfers.plan(&SSD1306, &frame, shown.as_ref());
+            diffed.run(&transfers);
+            diffed_bytes += transfers.bytes();
+
+            assert_eq!(&full.glass(0, PAGES)[..], frame.pixels(), "full flush, step {}", step);
+            assert_eq!(&diffed.glass(0, PAGES)[..], frame.pixels(), "diff, step {}", step);
+            shown = Some(frame);
+        }
+        // new pictures and scattered noise cost as much as ever
+        assert!(diffed_bytes < full_bytes * 2 / 3);
+    }
+
+    #[test]
+    fn sh1106_windows_land_past_the_offset() {
+        let panel = Panel::SH1106_128X64;
+        let mut ram = Gddram::new();
+        let mut transfers = Transfers::new();
+        let mut frame = Frame::new();
+        transfers.plan(&panel, &frame, None);
+        ram.run(&transfers);
+        let shown = frame.clone();
+        Circle::new(Point::new(90, 20), 30)
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut frame)
+            .unwrap();
+        frame.set(0, 0, true);
+        frame.set(127, 63, true);
+        transfers.plan(&panel, &frame, Some(&shown));
+        ram.run(&transfers);
+        assert_eq!(&ram.glass(2, PAGES)[..], frame.pixels());
+    }
+
+    #[test]
+    fn short_panels_send_half_the_pages() {
+        let panel = Panel::SSD1306_128X32;
+        let mut frame = panel.frame();
+        assert_eq!(frame.size(), Size::new(128, 32));
+        frame.clear(BinaryColor::On).unwrap();
+        frame.set(3, 40, false);
+        assert!(!frame.get(3, 40));
+        let mut ram = Gddram::new();
+        let mut transfers = Transfers::new();
+        transfers.plan(&panel, &frame, None);
+        assert_eq!(transfers.len(), 2 * 4);
+        ram.run(&transfers);
+        assert_eq!(&ram.glass(0, 4)[..], frame.pixels());
+    }
+}
@@ -0,0 +1,550 @@
+//! Galleries uploaded over serial into two flash slots, A and B.
+//!
+//! An upload always goes into the slot that isn't showing, and only counts
+//! once its commit word is programmed, which happens after the whole slot
+//! has been read back and checked against the host's CRC. Losing power
+//! anywhere before that leaves an uncommitted slot that [`Gallery::select`]
+//! ignores, so the previous gallery keeps showing.
+//!
+//! Slot layout, all little endian:
+//!
+//! ```text
+//! 0   magic  u32  "ALPH"
+//! 4   seq    u32  bumped each upload, the highest committed one wins
+//! 8   frames u16
+//! 10  size   u16  bytes per frame, one or two planes
+//! 12  crc32  u32  of all the frames
+//! 16  commit u32  0 once committed, erased flash reads 0xffffffff
+//! 20  width  u16  pixels
+//! 22  height u16
+//! 24  format u16  FORMAT, anything else isn't shown until it's uploaded
+//!                 again
+//! 32  frames...
+//! ```
+//!
+//! Frames are rows, MSB first, like `.gray` files. A gallery doesn't have
+//! to match the panel, see `picture::Picture::draw_fitted`.
+
+use crate::protocol::{crc32, Error, Upload};
+
+/// The biggest 1bpp bitplane, 128x64. Plain frames are one plane, 4 level
+/// gray frames two, see `gray`.
+pub const PLANE: usize = 1024;
+pub const MAX_PLANES: usize = 2;
+pub const HEADER: usize = 32;
+
+const MAGIC: u32 = 0x4850_4c41;
+const COMMITTED: u32 = 0;
+/// The header's layout, bumped when it changes. Slots from before it was
+/// there read as erased, 0xffff.
+const FORMAT: u16 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Slot {
+    A,
+    B,
+}
+
+impl Slot {
+    pub fn other(self) -> Slot {
+        match self {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A,
+        }
+    }
+}
+
+/// The board's view of the flash set aside for galleries.
+pub trait Flash {
+    /// Bytes in one slot.
+    fn capacity(&self) -> usize;
+    /// Erase the whole slot. Failures are `Error::Flash`.
+    fn erase(&mut self, slot: Slot) -> Result<(), Error>;
+    /// Program `data` at `offset` into the slot, which must be erased there.
+    fn program(&mut self, slot: Slot, offset: usize, data: &[u8]) -> Result<(), Error>;
+    /// The slot's contents, memory mapped.
+    fn read(&self, slot: Slot) -> &[u8];
+}
+
+fn word(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn half(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+/// How many frames of `size` bytes fit in a slot.
+pub fn slot_frames<F: Flash>(flash: &F, size: usize) -> usize {
+    (flash.capacity() - HEADER) / size
+}
+
+/// Bytes in one plane of a picture that size, if it's one we can show.
+fn plane_size(width: usize, height: usize) -> Option<usize> {
+    let size = width * height / 8;
+    if width > 0 && height > 0 && width.is_multiple_of(8) && size <= PLANE {
+        Some(size)
+    } else {
+        None
+    }
+}
+
+/// The committed gallery in a slot, if there is one.
+fn committed<F: Flash>(flash: &F, slot: Slot) -> Option<Gallery> {
+    let bytes = flash.read(slot);
+    let frames = half(bytes, 8) as usize;
+    let size = half(bytes, 10) as usize;
+    if half(bytes, 24) != FORMAT {
+        return None;
+    }
+    let (width, height) = (half(bytes, 20) as usize, half(bytes, 22) as usize);
+    let plane = plane_size(width, height)?;
+    let planes = size / plane;
+    let ok = word(bytes, 0) == MAGIC
+        && word(bytes, 16) == COMMITTED
+        && planes * plane == size
+        && (1..=MAX_PLANES).contains(&planes)
+        && frames > 0
+        && frames <= slot_frames(flash, size);
+    if ok {
+        Some(Gallery {
+            slot,
+            seq: word(bytes, 4),
+            frames,
+            planes,
+            width,
+            height,
+        })
+    } else {
+        None
+    }
+}
+
+/// The committed gallery currently showing, if any.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Gallery {
+    pub slot: Slot,
+    pub seq: u32,
+    pub frames: usize,
+    pub planes: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Gallery {
+    /// Pick the newest committed slot.
+    pub fn select<F: Flash>(flash: &F) -> Option<Gallery> {
+        let a = committed(flash, Slot::A);
+        let b = committed(flash, Slot::B);
+        match (a, b) {
+            // wrapping compare, in case anyone uploads four billion times
+            (Some(a), Some(b)) if (b.seq.wrapping_sub(a.seq) as i32) > 0 => Some(b),
+            (Some(a), _) => Some(a),
+            (None, b) => b,
+        }
+    }
+
+    /// Pick the newest committed slot that's the same way round as a
+    /// `width` x `height` panel, or the newest if neither is. Upload a
+    /// portrait and a landscape gallery and turning the panel picks one.
+    pub fn select_facing<F: Flash>(flash: &F, width: usize, height: usize) -> Option<Gallery> {
+        let newest = Self::select(flash)?;
+        let portrait = height > width;
+        if newest.is_portrait() == portrait {
+            return Some(newest);
+        }
+        let other = match newest.slot {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A,
+        };
+        match committed(flash, other) {
+            Some(g) if g.is_portrait() == portrait => Some(g),
+            _ => Some(newest),
+        }
+    }
+
+    /// Taller than it is wide.
+    pub fn is_portrait(&self) -> bool {
+        self.height > self.width
+    }
+
+    /// Frame `i`, its planes one after the other.
+    pub fn frame<'f, F: Flash>(&self, flash: &'f F, i: usize) -> &'f [u8] {
+        let size = self.planes * self.width * self.height / 8;
+        let start = HEADER + i * size;
+        &flash.read(self.slot)[start..start + size]
+    }
+
+    /// Plane `p` of frame `i`, ready for an `ImageRaw` `width` wide.
+    pub fn plane<'f, F: Flash>(&self, flash: &'f F, i: usize, p: usize) -> &'f [u8] {
+        let size = self.width * self.height / 8;
+        &self.frame(flash, i)[p * size..(p + 1) * size]
+    }
+}
+
+/// Receives an upload into the slot that isn't showing.
+pub struct Uploader {
+    slot: Slot,
+    seq: u32,
+    frames: usize,
+    planes: usize,
+    width: usize,
+    height: usize,
+    written: usize,
+    active: bool,
+}
+
+impl Uploader {
+    pub const fn new() -> Self {
+        Uploader {
+            slot: Slot::A,
+            seq: 0,
+            frames: 0,
+            planes: 1,
+            width: 128,
+            height: 64,
+            written: 0,
+            active: false,
+        }
+    }
+
+    /// Handle one upload request. Returns the new gallery once a commit
+    /// checks out, the caller switches to it.
+    pub fn handle<F: Flash>(
+        &mut self,
+        flash: &mut F,
+        current: Option<Gallery>,
+        upload: Upload,
+    ) -> Result<Option<Gallery>, Error> {
+        match upload {
+            Upload::Begin {
+                frames,
+                planes,
+                width,
+                height,
+            } => {
+                let size = (planes as usize, width as usize, height as usize);
+                self.begin(flash, current, frames as usize, size).map(|_| None)
+            }
+            Upload::Chunk { offset, data } => self.chunk(flash, offset as usize, data).map(|_| None),
+            Upload::Commit { crc } => self.commit(flash, crc).map(Some),
+        }
+    }
+
+    fn begin<F: Flash>(
+        &mut self,
+        flash: &mut F,
+        current: Option<Gallery>,
+        frames: usize,
+        (planes, width, height): (usize, usize, usize),
+    ) -> Result<(), Error> {
+        self.active = false;
+        let plane = plane_size(width, height).ok_or(Error::Invalid)?;
+        if !(1..=MAX_PLANES).contains(&planes)
+            || frames == 0
+            || frames > slot_frames(flash, planes * plane)
+        {
+            return Err(Error::Invalid);
+        }
+        let (slot, seq) = match current {
+            Some(g) => (g.slot.other(), g.seq.wrapping_add(1)),
+            None => (Slot::A, 1),
+        };
+        flash.erase(slot)?;
+
+        let mut header = [0u8; 12];
+        header[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        header[4..8].copy_from_slice(&seq.to_le_bytes());
+        header[8..10].copy_from_slice(&(frames as u16).to_le_bytes());
+        header[10..12].copy_from_slice(&((planes * plane) as u16).to_le_bytes());
+        flash.program(slot, 0, &header)?;
+        let mut size = [0u8; 6];
+        size[0..2].copy_from_slice(&(width as u16).to_le_bytes());
+        size[2..4].copy_from_slice(&(height as u16).to_le_bytes());
+        size[4..6].copy_from_slice(&FORMAT.to_le_bytes());
+        flash.program(slot, 20, &size)?;
+
+        *self = Uploader {
+            slot,
+            seq,
+            frames,
+            planes,
+            width,
+            height,
+            written: 0,
+            active: true,
+        };
+        Ok(())
+    }
+
+    fn chunk<F: Flash>(&mut self, flash: &mut F, offset: usize, data: &[u8]) -> Result<(), Error> {
+        // in order only, a resent chunk after a lost reply is fine though
+        if !self.active || offset + data.len() > self.len() {
+            return Err(Error::Sequence);
+        }
+        if offset + data.len() == self.written {
+            return Ok(());
+        }
+        if offset != self.written {
+            return Err(Error::Sequence);
+        }
+        flash.program(self.slot, HEADER + offset, data)?;
+        self.written += data.len();
+        Ok(())
+    }
+
+    fn commit<F: Flash>(&mut self, flash: &mut F, crc: u32) -> Result<Gallery, Error> {
+        if !self.active || self.written != self.len() {
+            return Err(Error::Sequence);
+        }
+        self.active = false;
+        let start = HEADER;
+        let end = HEADER + self.written;
+        if crc32(0, &flash.read(self.slot)[start..end]) != crc {
+            return Err(Error::Crc);
+        }
+        flash.program(self.slot, 12, &crc.to_le_bytes())?;
+        flash.program(self.slot, 16, &COMMITTED.to_le_bytes())?;
+        Ok(Gallery {
+            slot: self.slot,
+            seq: self.seq,
+            frames: self.frames,
+            planes: self.planes,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    // bytes in the whole upload
+    fn len(&self) -> usize {
+        self.frames * self.planes * self.width * self.height / 8
+    }
+}
+
+impl Default for Uploader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOT: usize = HEADER + 4 * PLANE;
+
+    /// Flash in RAM, with the same only-clear-bits rule as the real thing.
+    struct RamFlash {
+        a: [u8; SLOT],
+        b: [u8; SLOT],
+    }
+
+    impl RamFlash {
+        fn new() -> Self {
+            RamFlash {
+                a: [0xff; SLOT],
+                b: [0xff; SLOT],
+            }
+        }
+
+        fn slot_mut(&mut self, slot: Slot) -> &mut [u8; SLOT] {
+            match slot {
+                Slot::A => &mut self.a,
+                Slot::B => &mut self.b,
+            }
+        }
+    }
+
+    impl Flash for RamFlash {
+        fn capacity(&self) -> usize {
+            SLOT
+        }
+
+        fn erase(&mut self, slot: Slot) -> Result<(), Error> {
+            *self.slot_mut(slot) = [0xff; SLOT];
+            Ok(())
+        }
+
+        fn program(&mut self, slot: Slot, offset: usize, data: &[u8]) -> Result<(), Error> {
+            for (cell, &b) in self.slot_mut(slot)[offset..].iter_mut().zip(data) {
+                *cell &= b;
+            }
+            Ok(())
+        }
+
+        fn read(&self, slot: Slot) -> &[u8] {
+            match slot {
+                Slot::A => &self.a,
+                Slot::B => &self.b,
+            }
+        }
+    }
+
+    fn begin_128x64(frames: u16, planes: u8) -> Upload<'static> {
+        Upload::Begin {
+            frames,
+            planes,
+            width: 128,
+            height: 64,
+        }
+    }
+
+    fn upload(flash: &mut RamFlash, fill: u8, frames: usize, commit: bool) -> Option<Gallery> {
+        upload_planes(flash, fill, frames, 1, commit)
+    }
+
+    fn upload_planes(
+        flash: &mut RamFlash,
+        fill: u8,
+        frames: usize,
+        planes: usize,
+        commit: bool,
+    ) -> Option<Gallery> {
+        upload_sized(flash, fill, frames, planes, (128, 64), commit)
+    }
+
+    fn upload_sized(
+        flash: &mut RamFlash,
+        fill: u8,
+        frames: usize,
+        planes: usize,
+        (width, height): (usize, usize),
+        commit: bool,
+    ) -> Option<Gallery> {
+        let current = Gallery::select(flash);
+        let mut uploader = Uploader::new();
+        let data = [fill; 64];
+        let begin = Upload::Begin {
+            frames: frames as u16,
+            planes: planes as u8,
+            width: width as u8,
+            height: height as u8,
+        };
+        uploader.handle(flash, current, begin).unwrap();
+        let mut crc = 0;
+        for offset in (0..frames * planes * width * height / 8).step_by(data.len()) {
+            let chunk = Upload::Chunk {
+                offset: offset as u32,
+                data: &data,
+            };
+            uploader.handle(flash, current, chunk).unwrap();
+            crc = crc32(crc, &data);
+        }
+        if commit {
+            uploader.handle(flash, current, Upload::Commit { crc }).unwrap()
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn uploads_alternate_slots() {
+        let mut flash = RamFlash::new();
+        assert_eq!(Gallery::select(&flash), None);
+
+        let first = upload(&mut flash, 0x11, 2, true).unwrap();
+        assert_eq!(first.slot, Slot::A);
+        assert_eq!(Gallery::select(&flash), Some(first));
+
+        let second = upload(&mut flash, 0x22, 3, true).unwrap();
+        assert_eq!(second.slot, Slot::B);
+        assert_eq!(Gallery::select(&flash), Some(second));
+        assert_eq!(second.frame(&flash, 2)[0], 0x22);
+    }
+
+    #[test]
+    fn interrupted_upload_keeps_the_old_gallery() {
+        let mut flash = RamFlash::new();
+        let first = upload(&mut flash, 0x11, 2, true).unwrap();
+        // power goes out before the commit
+        upload(&mut flash, 0x22, 4, false);
+        assert_eq!(Gallery::select(&flash), Some(first));
+        assert_eq!(first.frame(&flash, 1)[0], 0x11);
+    }
+
+    #[test]
+    fn bad_crc_is_not_committed() {
+        let mut flash = RamFlash::new();
+        let mut uploader = Uploader::new();
+        let begin = begin_128x64(1, 1);
+        uploader.handle(&mut flash, None, begin).unwrap();
+        let data = [0x33; PLANE];
+        let chunk = Upload::Chunk { offset: 0, data: &data };
+        uploader.handle(&mut flash, None, chunk).unwrap();
+        let commit = Upload::Commit { crc: 0xdead_beef };
+        assert_eq!(uploader.handle(&mut flash, None, commit), Err(Error::Crc));
+        assert_eq!(Gallery::select(&flash), None);
+    }
+
+    #[test]
+    fn chunks_must_arrive_in_order() {
+        let mut flash = RamFlash::new();
+        let mut uploader = Uploader::new();
+        let data = [0x44; 256];
+        let late = Upload::Chunk { offset: 256, data: &data };
+        assert_eq!(uploader.handle(&mut flash, None, late), Err(Error::Sequence));
+        let begin = begin_128x64(1, 1);
+        uploader.handle(&mut flash, None, begin).unwrap();
+        assert_eq!(uploader.handle(&mut flash, None, late), Err(Error::Sequence));
+    }
+
+    #[test]
+    fn gray_frames_take_two_planes() {
+        let mut flash = RamFlash::new();
+        let gray = upload_planes(&mut flash, 0x55, 2, 2, true).unwrap();
+        assert_eq!(gray.planes, 2);
+        assert_eq!(Gallery::select(&flash), Some(gray));
+        assert_eq!(gray.frame(&flash, 1).len(), 2 * PLANE);
+
+        // four planes' worth of slot, so three gray frames don't fit
+        let mut uploader = Uploader::new();
+        let begin = begin_128x64(3, 2);
+        assert_eq!(uploader.handle(&mut flash, Some(gray), begin), Err(Error::Invalid));
+    }
+
+    #[test]
+    fn galleries_keep_their_size() {
+        let mut flash = RamFlash::new();
+        let strip = upload_sized(&mut flash, 0x66, 7, 1, (128, 32), true).unwrap();
+        assert_eq!((strip.width, strip.height), (128, 32));
+        assert_eq!(Gallery::select(&flash), Some(strip));
+        assert_eq!(strip.frame(&flash, 6).len(), PLANE / 2);
+
+        let gray = upload_sized(&mut flash, 0x77, 2, 2, (64, 120), true).unwrap();
+        assert_eq!(gray.plane(&flash, 1, 1).len(), 64 * 120 / 8);
+
+        // rows have to be whole bytes, and fit in a plane
+        let mut uploader = Uploader::new();
+        for &(width, height) in &[(100, 32), (128, 72), (0, 64)] {
+            let begin = Upload::Begin { frames: 1, planes: 1, width, height };
+            assert_eq!(uploader.handle(&mut flash, Some(gray), begin), Err(Error::Invalid));
+        }
+    }
+
+    #[test]
+    fn other_formats_are_not_shown() {
+        let mut flash = RamFlash::new();
+        let old = upload(&mut flash, 0x55, 1, true).unwrap();
+        let new = upload(&mut flash, 0x66, 1, true).unwrap();
+        assert_eq!(Gallery::select(&flash), Some(new));
+        // a slot from before the format, which left it erased
+        flash.b[24..26].copy_from_slice(&[0xff; 2]);
+        assert_eq!(Gallery::select(&flash), Some(old));
+        // and one from after
+        flash.a[24..26].copy_from_slice(&(FORMAT + 1).to_le_bytes());
+        assert_eq!(Gallery::select(&flash), None);
+    }
+
+    #[test]
+    fn turning_the_panel_picks_the_gallery_facing_the_same_way() {
+        let mut flash = RamFlash::new();
+        let portrait = upload_sized(&mut flash, 0x11, 2, 1, (64, 128), true).unwrap();
+        let landscape = upload(&mut flash, 0x22, 2, true).unwrap();
+        assert_eq!(Gallery::select_facing(&flash, 128, 64), Some(landscape));
+        assert_eq!(Gallery::select_facing(&flash, 64, 128), Some(portrait));
+
+        // nothing facing the right way, so the newest it is
+        let square = upload_sized(&mut flash, 0x33, 2, 1, (64, 64), true).unwrap();
+        assert_eq!(Gallery::select_facing(&flash, 64, 128), Some(square));
+    }
+}
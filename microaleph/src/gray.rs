@@ -0,0 +1,86 @@
+//! 4 level grayscale on a 1bpp panel by flipping between bitplanes.
+//!
+//! A gray frame is two 1bpp planes back to back, the high bit of each
+//! pixel's level first. The high plane stays up twice as long as the low
+//! one, so a pixel at level 3 is lit all the time, 2 two thirds of it,
+//! 1 a third, and 0 never.
+//!
+//! Every swap is a full flush, which is why boards only do this behind
+//! their `gray4` feature: it keeps the bus busy and the MCU awake.
+
+pub const PLANES: usize = 2;
+
+/// Ticks each plane stays up, high plane first.
+pub const WEIGHTS: [u8; PLANES] = [2, 1];
+
+/// Plane `p` of a gray frame, whatever size it is.
+pub fn plane(frame: &[u8], p: usize) -> &[u8] {
+    let size = frame.len() / PLANES;
+    &frame[p * size..(p + 1) * size]
+}
+
+/// Which plane should be up.
+pub struct Dither {
+    plane: usize,
+    left: u8,
+}
+
+impl Dither {
+    pub const fn new() -> Self {
+        Dither {
+            plane: 0,
+            left: WEIGHTS[0],
+        }
+    }
+
+    /// Start over on the high plane, for a new picture.
+    pub fn restart(&mut self) {
+        *self = Dither::new();
+    }
+
+    /// A tick went by. Returns the plane to flush if it's time to swap.
+    pub fn tick(&mut self) -> Option<usize> {
+        self.left -= 1;
+        if self.left > 0 {
+            return None;
+        }
+        self.plane = (self.plane + 1) % PLANES;
+        self.left = WEIGHTS[self.plane];
+        Some(self.plane)
+    }
+}
+
+impl Default for Dither {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gallery::PLANE;
+
+    #[test]
+    fn planes_get_their_weight() {
+        let mut dither = Dither::new();
+        let mut up = [0; PLANES];
+        let mut showing = 0;
+        for _ in 0..30 {
+            up[showing] += 1;
+            if let Some(p) = dither.tick() {
+                showing = p;
+            }
+        }
+        assert_eq!(up, [20, 10]);
+    }
+
+    #[test]
+    fn planes_split_a_frame() {
+        let mut frame = [0u8; 2 * PLANE];
+        frame[PLANE] = 0xaa;
+        assert_eq!(plane(&frame, 0)[0], 0);
+        assert_eq!(plane(&frame, 1)[0], 0xaa);
+        assert_eq!(plane(&frame, 1).len(), PLANE);
+    }
+}
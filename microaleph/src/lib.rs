@@ -0,0 +1,28 @@
+//! Code shared by the microaleph firmware.
+//!
+//! Everything in here is `no_std` and board agnostic. The firmware crate
+//! (`microaleph_fw`, one board module per board) owns the HAL and the
+//! pins, and hands displays and buses to these helpers.
+#![no_std]
+
+#[macro_use]
+mod log;
+
+pub mod brightness;
+pub mod burnin;
+pub mod fault;
+pub mod frame;
+pub mod gallery;
+pub mod gray;
+pub mod motion;
+pub mod oled;
+pub mod picture;
+pub mod protocol;
+pub mod recover;
+pub mod ring;
+pub mod rotation;
+pub mod scan;
+pub mod slideshow;
+#[cfg(feature = "usb")]
+pub mod usb;
+pub mod window;
@@ -0,0 +1,41 @@
+//! Logging macros that compile to defmt calls when the `defmt` feature is
+//! on and to nothing when it is off, so firmware can log unconditionally.
+//!
+//! Boards enabling `microaleph/defmt` must also depend on `defmt` and
+//! `defmt-rtt` themselves, the defmt macros expand to `::defmt` paths.
+
+#[cfg(feature = "defmt")]
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => { ::defmt::info!($($arg)*) };
+}
+
+#[cfg(feature = "defmt")]
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => { ::defmt::warn!($($arg)*) };
+}
+
+#[cfg(feature = "defmt")]
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => { ::defmt::error!($($arg)*) };
+}
+
+#[cfg(not(feature = "defmt"))]
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {{}};
+}
+
+#[cfg(not(feature = "defmt"))]
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {{}};
+}
+
+#[cfg(not(feature = "defmt"))]
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {{}};
+}
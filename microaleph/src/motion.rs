@@ -0,0 +1,476 @@
+//! Which way the board's held, and how it's turning, from motion sensors.
+//!
+//! [`Motion`] turns accelerometer samples into what the slideshow cares
+//! about: which way up the panel is, a shake to move on a picture, and
+//! lying face down to pause. [`Lsm303dlhc`] reads the samples from the
+//! accelerometer half of an LSM303DLHC, like the one on the F3 Discovery.
+//! [`L3gd20`] reads how fast it's turning from the gyro next to it, for
+//! `window`.
+//!
+//! Axes are the sensor's: X to the right and Y up with the board flat in
+//! front of you, Z out of its face. Each reads +1g when it points up.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use embedded_hal::blocking::spi::Transfer;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::rotation::Rotation;
+
+/// An accelerometer reading, in mg.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Sample {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+/// What changed about how the board's held.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event {
+    /// Held another way up, turn the pictures by this much to match.
+    Turned(Rotation),
+    /// Shaken, on to the next picture.
+    Shaken,
+    FaceDown,
+    FaceUp,
+}
+
+/// Thresholds and timings, in mg and samples. The defaults suit 50
+/// samples a second.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Config {
+    /// How far from 1g a sample has to be to count as a jolt.
+    pub jolt_mg: u16,
+    /// How many jolts make a shake.
+    pub jolts: u8,
+    /// How many samples those jolts have to fall in.
+    pub window: u8,
+    /// How many samples after a shake before anything else counts, while
+    /// the hand settles.
+    pub settle: u8,
+    /// How much of 1g an axis needs to say which way up the board is.
+    pub tilt_mg: u16,
+    /// How many samples a new way up has to last before it counts.
+    pub hold: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            jolt_mg: 800,
+            jolts: 3,
+            window: 25,
+            settle: 50,
+            tilt_mg: 600,
+            hold: 25,
+        }
+    }
+}
+
+// what the board's doing, before it's held long enough to count
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Pose {
+    Upright(Rotation),
+    FaceDown,
+    // flat on its back, or somewhere in between
+    Other,
+}
+
+pub struct Motion {
+    config: Config,
+    rotation: Rotation,
+    face_down: bool,
+    pose: Pose,
+    held: u8,
+    jolts: u8,
+    window: u8,
+    settle: u8,
+}
+
+impl Motion {
+    pub fn new(config: Config) -> Self {
+        Motion {
+            config,
+            rotation: Rotation::Rotate0,
+            face_down: false,
+            pose: Pose::Other,
+            held: 0,
+            jolts: 0,
+            window: 0,
+            settle: 0,
+        }
+    }
+
+    /// Which way up the pictures are turned, as of the last `Turned`.
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    fn pose(&self, s: Sample) -> Pose {
+        let tilt = self.config.tilt_mg as i32;
+        let (x, y, z) = (s.x as i32, s.y as i32, s.z as i32);
+        if z < -tilt {
+            Pose::FaceDown
+        } else if z.abs() >= tilt || x.abs().max(y.abs()) < tilt {
+            Pose::Other
+        } else if y.abs() >= x.abs() {
+            // the top edge up is how it's drawn, upside down is half a turn
+            Pose::Upright(if y > 0 { Rotation::Rotate0 } else { Rotation::Rotate180 })
+        } else {
+            // its right edge up means the board went a quarter turn
+            // anticlockwise, so the picture goes a quarter turn clockwise
+            Pose::Upright(if x > 0 { Rotation::Rotate90 } else { Rotation::Rotate270 })
+        }
+    }
+
+    fn jolt(&self, s: Sample) -> bool {
+        let (x, y, z) = (s.x as i32, s.y as i32, s.z as i32);
+        let g2 = x * x + y * y + z * z;
+        let high = 1000 + self.config.jolt_mg as i32;
+        let low = (1000 - self.config.jolt_mg as i32).max(0);
+        g2 > high * high || g2 < low * low
+    }
+
+    /// A new sample. Returns what changed, if anything.
+    pub fn update(&mut self, sample: Sample) -> Option<Event> {
+        if self.settle > 0 {
+            self.settle -= 1;
+            return None;
+        }
+
+        let jolt = self.jolt(sample);
+        if self.window > 0 {
+            self.window -= 1;
+        } else {
+            self.jolts = 0;
+        }
+        if jolt {
+            if self.jolts == 0 {
+                self.window = self.config.window;
+            }
+            self.jolts += 1;
+            if self.jolts >= self.config.jolts {
+                self.jolts = 0;
+                self.window = 0;
+                self.settle = self.config.settle;
+                self.held = 0;
+                return Some(Event::Shaken);
+            }
+            // which way up it is means nothing while it's moving
+            self.held = 0;
+            return None;
+        }
+
+        let pose = self.pose(sample);
+        if pose != self.pose {
+            self.pose = pose;
+            self.held = 0;
+        }
+        if self.held < self.config.hold {
+            self.held += 1;
+            return None;
+        }
+        match pose {
+            Pose::FaceDown if !self.face_down => {
+                self.face_down = true;
+                Some(Event::FaceDown)
+            }
+            Pose::FaceDown => None,
+            _ if self.face_down => {
+                self.face_down = false;
+                Some(Event::FaceUp)
+            }
+            Pose::Upright(rotation) if rotation != self.rotation => {
+                self.rotation = rotation;
+                Some(Event::Turned(rotation))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The LSM303DLHC's accelerometer, always at this address.
+pub const LSM303DLHC: u8 = 0x19;
+
+const CTRL_REG1_A: u8 = 0x20;
+const CTRL_REG4_A: u8 = 0x23;
+const OUT_X_L_A: u8 = 0x28;
+// set on a register address to read on through the ones after it
+const AUTO_INCREMENT: u8 = 0x80;
+
+/// The accelerometer half of an LSM303DLHC, sampling at 50Hz.
+pub struct Lsm303dlhc<I> {
+    i2c: I,
+}
+
+impl<I, E> Lsm303dlhc<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Wakes it up: 50Hz, all three axes, +-2g at 1mg a step.
+    pub fn new(mut i2c: I) -> Result<Self, E> {
+        i2c.write(LSM303DLHC, &[CTRL_REG1_A, 0x47])?;
+        // high resolution
+        i2c.write(LSM303DLHC, &[CTRL_REG4_A, 0x08])?;
+        Ok(Lsm303dlhc { i2c })
+    }
+
+    /// The latest sample.
+    pub fn read(&mut self) -> Result<Sample, E> {
+        let mut buf = [0; 6];
+        self.i2c
+            .write_read(LSM303DLHC, &[OUT_X_L_A | AUTO_INCREMENT], &mut buf)?;
+        // 12 bits, left justified
+        let axis = |i: usize| i16::from_le_bytes([buf[i], buf[i + 1]]) >> 4;
+        Ok(Sample {
+            x: axis(0),
+            y: axis(2),
+            z: axis(4),
+        })
+    }
+
+    pub fn release(self) -> I {
+        self.i2c
+    }
+}
+
+/// How fast the board's turning about each axis, in millidegrees a
+/// second, anticlockwise looking down the axis.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Rates {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+// what WHO_AM_I says on the L3GD20, the L3GD20H and the I3G4250D that
+// later F3 Discoveries have instead, which all work the same
+const GYROS: [u8; 3] = [0xd4, 0xd7, 0xd3];
+const WHO_AM_I: u8 = 0x0f;
+const CTRL_REG1: u8 = 0x20;
+const OUT_X_L: u8 = 0x28;
+// on the register address: read rather than write, and carry on to the
+// registers after it
+const READ: u8 = 0x80;
+const MULTIPLE: u8 = 0x40;
+// 8.75 millidegrees a second a step at +-250 degrees a second, in
+// hundredths
+const STEP: i32 = 875;
+
+/// An L3GD20 gyro on SPI, mode 3, sampling at 95Hz.
+pub struct L3gd20<S, CS> {
+    spi: S,
+    cs: CS,
+}
+
+impl<S, CS, E> L3gd20<S, CS>
+where
+    S: Transfer<u8, Error = E>,
+    CS: OutputPin,
+{
+    /// Wakes it up, if it's there: `None` if nothing that says it's one
+    /// answers.
+    pub fn new(spi: S, cs: CS) -> Option<Self> {
+        let mut gyro = L3gd20 { spi, cs };
+        let mut who = [WHO_AM_I | READ, 0];
+        gyro.transfer(&mut who).ok()?;
+        if !GYROS.contains(&who[1]) {
+            return None;
+        }
+        // 95Hz, all three axes, out of power down
+        gyro.transfer(&mut [CTRL_REG1, 0x0f]).ok()?;
+        Some(gyro)
+    }
+
+    fn transfer(&mut self, buf: &mut [u8]) -> Result<(), E> {
+        self.cs.set_low().ok();
+        let result = self.spi.transfer(buf).map(|_| ());
+        self.cs.set_high().ok();
+        result
+    }
+
+    /// The latest rates.
+    pub fn read(&mut self) -> Result<Rates, E> {
+        let mut buf = [0; 7];
+        buf[0] = OUT_X_L | READ | MULTIPLE;
+        self.transfer(&mut buf)?;
+        // the first byte back is the one that went out with the address
+        let axis = |i: usize| i16::from_le_bytes([buf[i], buf[i + 1]]) as i32 * STEP / 100;
+        Ok(Rates {
+            x: axis(1),
+            y: axis(3),
+            z: axis(5),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    const FLAT: Sample = Sample { x: 0, y: 0, z: 1000 };
+    const UPRIGHT: Sample = Sample { x: 0, y: 1000, z: 0 };
+    const RIGHT_UP: Sample = Sample { x: 980, y: -50, z: 150 };
+    const FACE_DOWN: Sample = Sample { x: 30, y: 0, z: -990 };
+    const JOLT: Sample = Sample { x: 1500, y: 1000, z: 900 };
+
+    fn feed(motion: &mut Motion, sample: Sample, n: usize) -> Option<Event> {
+        (0..n).filter_map(|_| motion.update(sample)).last()
+    }
+
+    #[test]
+    fn turning_follows_the_way_up() {
+        let mut motion = Motion::new(Config::default());
+        assert_eq!(feed(&mut motion, UPRIGHT, 100), None);
+        assert_eq!(feed(&mut motion, RIGHT_UP, 10), None);
+        assert_eq!(
+            feed(&mut motion, RIGHT_UP, 20),
+            Some(Event::Turned(Rotation::Rotate90))
+        );
+        // lying flat keeps the last way up
+        assert_eq!(feed(&mut motion, FLAT, 100), None);
+        assert_eq!(motion.rotation(), Rotation::Rotate90);
+        let upside_down = Sample { x: 0, y: -1000, z: 0 };
+        assert_eq!(
+            feed(&mut motion, upside_down, 30),
+            Some(Event::Turned(Rotation::Rotate180))
+        );
+    }
+
+    #[test]
+    fn three_jolts_are_a_shake() {
+        let mut motion = Motion::new(Config::default());
+        feed(&mut motion, FLAT, 30);
+        assert_eq!(motion.update(JOLT), None);
+        assert_eq!(feed(&mut motion, FLAT, 5), None);
+        assert_eq!(motion.update(JOLT), None);
+        assert_eq!(motion.update(JOLT), Some(Event::Shaken));
+        // and the shaking that follows doesn't count twice
+        assert_eq!(feed(&mut motion, JOLT, 50), None);
+    }
+
+    #[test]
+    fn jolts_too_far_apart_are_not_a_shake() {
+        let mut motion = Motion::new(Config::default());
+        for _ in 0..5 {
+            assert_eq!(motion.update(JOLT), None);
+            assert_eq!(feed(&mut motion, FLAT, 30), None);
+        }
+    }
+
+    #[test]
+    fn face_down_and_back() {
+        let mut motion = Motion::new(Config::default());
+        feed(&mut motion, FLAT, 30);
+        assert_eq!(feed(&mut motion, FACE_DOWN, 30), Some(Event::FaceDown));
+        assert_eq!(feed(&mut motion, FACE_DOWN, 30), None);
+        assert_eq!(feed(&mut motion, FLAT, 30), Some(Event::FaceUp));
+    }
+
+    // answers reads from OUT_X_L_A onwards
+    struct Sensor {
+        registers: [u8; 0x40],
+        config: heapless::Vec<(u8, u8), 4>,
+    }
+
+    impl Write for Sensor {
+        type Error = ();
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+            assert_eq!(address, LSM303DLHC);
+            self.config.push((bytes[0], bytes[1])).ok();
+            Ok(())
+        }
+    }
+
+    impl WriteRead for Sensor {
+        type Error = ();
+
+        fn write_read(&mut self, address: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), ()> {
+            assert_eq!(address, LSM303DLHC);
+            let start = (bytes[0] & !AUTO_INCREMENT) as usize;
+            buf.copy_from_slice(&self.registers[start..start + buf.len()]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn samples_in_mg() {
+        let mut sensor = Sensor {
+            registers: [0; 0x40],
+            config: heapless::Vec::new(),
+        };
+        // +1000mg, -250mg and 0, each shifted up 4 bits
+        let out = [(1000i16 << 4), (-250i16 << 4), 0];
+        for (i, v) in out.iter().enumerate() {
+            let [lo, hi] = v.to_le_bytes();
+            sensor.registers[OUT_X_L_A as usize + 2 * i] = lo;
+            sensor.registers[OUT_X_L_A as usize + 2 * i + 1] = hi;
+        }
+        let mut accel = Lsm303dlhc::new(sensor).unwrap();
+        assert_eq!(accel.read(), Ok(Sample { x: 1000, y: -250, z: 0 }));
+        assert_eq!(&accel.release().config[..], &[(0x20, 0x47), (0x23, 0x08)]);
+    }
+
+    // an L3GD20 that says who it is and what it reads, as long as it's
+    // selected
+    struct Gyro<'a> {
+        who: u8,
+        out: [i16; 3],
+        cs: &'a Cell<bool>,
+        writes: heapless::Vec<(u8, u8), 4>,
+    }
+
+    impl Transfer<u8> for Gyro<'_> {
+        type Error = ();
+
+        fn transfer<'w>(&mut self, buf: &'w mut [u8]) -> Result<&'w [u8], ()> {
+            assert!(self.cs.get());
+            let register = buf[0] & !(READ | MULTIPLE);
+            if buf[0] & READ == 0 {
+                self.writes.push((register, buf[1])).ok();
+            } else if register == WHO_AM_I {
+                buf[1] = self.who;
+            } else if register == OUT_X_L {
+                for (i, v) in self.out.iter().enumerate() {
+                    buf[1 + 2 * i..3 + 2 * i].copy_from_slice(&v.to_le_bytes());
+                }
+            }
+            Ok(buf)
+        }
+    }
+
+    // selected while low
+    struct Cs<'a>(&'a Cell<bool>);
+
+    impl OutputPin for Cs<'_> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.0.set(true);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            self.0.set(false);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn gyro_rates_in_millidegrees() {
+        let cs = Cell::new(false);
+        let gyro = |who| Gyro {
+            who,
+            out: [100, -8, 0],
+            cs: &cs,
+            writes: heapless::Vec::new(),
+        };
+        assert!(L3gd20::new(gyro(0x00), Cs(&cs)).is_none());
+        let mut l3gd20 = L3gd20::new(gyro(0xd4), Cs(&cs)).unwrap();
+        assert_eq!(l3gd20.read(), Ok(Rates { x: 875, y: -70, z: 0 }));
+        assert_eq!(&l3gd20.spi.writes[..], &[(CTRL_REG1, 0x0f)]);
+        assert!(!cs.get());
+    }
+}
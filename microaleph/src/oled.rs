@@ -0,0 +1,197 @@
+//! The monochrome OLED controllers we can buy modules with, and a display
+//! that drives any of them from a [`Frame`].
+//!
+//! They all take the same page layout, eight rows to a column byte, and
+//! mostly the same commands. What differs:
+//!
+//! - SSD1306: charge pump on by command, horizontal addressing, 128x64 or
+//!   the 128x32 strips (which wire the COM pins differently).
+//! - SSD1309: the 2.42" modules, no charge pump (they bring 12V), a
+//!   faster clock, otherwise an SSD1306.
+//! - SH1106: the 1.3" modules. 132 columns of RAM with the glass on
+//!   columns 2..130, and only page addressing, so every window sets its
+//!   page and column itself.
+//!
+//! Boards pick a [`Panel`] at build time and hand it with their interface
+//! to [`Oled`], which does init and contrast. Pixels go out as the windows
+//! [`Frame`] works out, either straight from [`Oled::flush`] or as
+//! planned [`Transfers`](crate::frame::Transfers) for DMA.
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use heapless::Vec;
+
+use crate::frame::{Frame, Window, WIDTH};
+
+pub(crate) const COLUMN_ADDRESS: u8 = 0x21;
+pub(crate) const PAGE_ADDRESS: u8 = 0x22;
+pub(crate) const PAGE_START: u8 = 0xb0;
+pub(crate) const LOW_COLUMN: u8 = 0x00;
+pub(crate) const HIGH_COLUMN: u8 = 0x10;
+/// Commands in the longest [`Panel::address`].
+pub const ADDRESS_COMMANDS: usize = 6;
+
+/// Columns of SH1106 RAM left of the glass.
+const SH1106_OFFSET: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Controller {
+    Ssd1306,
+    Ssd1309,
+    Sh1106,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Panel {
+    pub controller: Controller,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Panel {
+    pub const SSD1306_128X64: Panel = Panel::new(Controller::Ssd1306, 64);
+    pub const SSD1306_128X32: Panel = Panel::new(Controller::Ssd1306, 32);
+    pub const SSD1309_128X64: Panel = Panel::new(Controller::Ssd1309, 64);
+    pub const SH1106_128X64: Panel = Panel::new(Controller::Sh1106, 64);
+
+    // they're all 128 columns of glass
+    const fn new(controller: Controller, height: usize) -> Self {
+        Panel {
+            controller,
+            width: WIDTH,
+            height,
+        }
+    }
+
+    pub fn pages(&self) -> usize {
+        self.height / 8
+    }
+
+    /// A frame this size.
+    pub fn frame(&self) -> Frame {
+        Frame::with_height(self.height)
+    }
+
+    /// Everything from power up to showing RAM, except the contrast.
+    pub fn init(&self) -> Vec<u8, 32> {
+        let mut commands = Vec::new();
+        let mut push = |bytes: &[u8]| commands.extend_from_slice(bytes).unwrap();
+        // display off
+        push(&[0xae]);
+        // clock divide and oscillator, the SSD1309 wants it faster
+        match self.controller {
+            Controller::Ssd1309 => push(&[0xd5, 0xa0]),
+            _ => push(&[0xd5, 0x80]),
+        }
+        // multiplex ratio, display offset and start line
+        push(&[0xa8, self.height as u8 - 1, 0xd3, 0x00, 0x40]);
+        match self.controller {
+            // charge pump on, horizontal addressing
+            Controller::Ssd1306 => push(&[0x8d, 0x14, 0x20, 0x00]),
+            Controller::Ssd1309 => push(&[0x20, 0x00]),
+            // DC-DC on, page addressing is all it has
+            Controller::Sh1106 => push(&[0xad, 0x8b]),
+        }
+        // segment remap and COM scan direction, so (0, 0) is top left
+        push(&[0xa1, 0xc8]);
+        // COM pins, the 32 row strips only wire every other one
+        match self.height {
+            32 => push(&[0xda, 0x02]),
+            _ => push(&[0xda, 0x12]),
+        }
+        // VCOMH deselect level
+        match self.controller {
+            Controller::Ssd1309 => push(&[0xdb, 0x34]),
+            _ => push(&[0xdb, 0x40]),
+        }
+        // show RAM, not inverted
+        push(&[0xa4, 0xa6]);
+        // no scrolling, the SH1106 doesn't have any
+        if self.controller != Controller::Sh1106 {
+            push(&[0x2e]);
+        }
+        // display on
+        push(&[0xaf]);
+        commands
+    }
+
+    /// Contrast, with the precharge to match.
+    pub fn contrast(&self, contrast: u8) -> [u8; 4] {
+        // precharge 1 is the panel's dimmest, anything brighter uses 2
+        let precharge = if contrast < 0x20 { 1 } else { 2 };
+        [0x81, contrast, 0xd9, (precharge << 4) | 1]
+    }
+
+    /// The commands pointing the controller at a window.
+    pub fn address(&self, w: &Window) -> Vec<u8, ADDRESS_COMMANDS> {
+        let mut commands = Vec::new();
+        match self.controller {
+            Controller::Ssd1306 | Controller::Ssd1309 => {
+                let (start, end, page) = (w.start as u8, (w.end - 1) as u8, w.page as u8);
+                commands
+                    .extend_from_slice(&[COLUMN_ADDRESS, start, end, PAGE_ADDRESS, page, page])
+                    .unwrap();
+            }
+            Controller::Sh1106 => {
+                let column = (w.start + SH1106_OFFSET) as u8;
+                commands
+                    .extend_from_slice(&[
+                        PAGE_START | w.page as u8,
+                        LOW_COLUMN | (column & 0x0f),
+                        HIGH_COLUMN | (column >> 4),
+                    ])
+                    .unwrap();
+            }
+        }
+        commands
+    }
+}
+
+/// A panel on an interface.
+pub struct Oled<DI> {
+    interface: DI,
+    panel: Panel,
+}
+
+impl<DI: WriteOnlyDataCommand> Oled<DI> {
+    pub fn new(interface: DI, panel: Panel) -> Self {
+        Oled { interface, panel }
+    }
+
+    pub fn panel(&self) -> &Panel {
+        &self.panel
+    }
+
+    pub fn interface(&self) -> &DI {
+        &self.interface
+    }
+
+    /// Back to the interface, say to recover a bus.
+    pub fn release(self) -> DI {
+        self.interface
+    }
+
+    /// Bring the panel up. The contrast goes back to the controller's
+    /// default, so set it again after.
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        let commands = self.panel.init();
+        self.interface.send_commands(DataFormat::U8(&commands))
+    }
+
+    pub fn contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        let commands = self.panel.contrast(contrast);
+        self.interface.send_commands(DataFormat::U8(&commands))
+    }
+
+    /// Send one window of `frame`.
+    pub fn draw(&mut self, frame: &Frame, w: &Window) -> Result<(), DisplayError> {
+        let commands = self.panel.address(w);
+        self.interface.send_commands(DataFormat::U8(&commands))?;
+        self.interface.send_data(DataFormat::U8(frame.window(w)))
+    }
+
+    /// Bring a panel showing `shown` up to `frame`, or send all of it if
+    /// nobody knows what it's showing.
+    pub fn flush(&mut self, frame: &Frame, shown: Option<&Frame>) -> Result<(), DisplayError> {
+        frame.windows(shown).try_for_each(|w| self.draw(frame, &w))
+    }
+}
@@ -0,0 +1,113 @@
+//! Pictures that don't match the panel they end up on.
+//!
+//! A gallery or the built in images are one size, the panel may be
+//! another: a 128x32 strip, or 128x64 showing a pack made for the strip.
+//! [`Picture::draw_fitted`] scales to fit, keeping the aspect ratio and
+//! centring what's left over, nearest neighbour so 1bpp stays crisp.
+
+use embedded_graphics::{
+    image::{Image, ImageRaw},
+    pixelcolor::BinaryColor,
+    prelude::*,
+};
+
+/// 1bpp rows, MSB first, like `.gray` files and gallery planes.
+#[derive(Clone, Copy)]
+pub struct Picture<'a> {
+    pub data: &'a [u8],
+    pub width: usize,
+    pub height: usize,
+}
+
+impl<'a> Picture<'a> {
+    pub fn new(data: &'a [u8], width: usize, height: usize) -> Self {
+        Picture { data, width, height }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let byte = self.data[(y * self.width + x) / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+
+    /// How big it comes out on a `width` x `height` target.
+    pub fn fitted(&self, width: usize, height: usize) -> (usize, usize) {
+        if width * self.height <= height * self.width {
+            (width, (self.height * width / self.width).max(1))
+        } else {
+            ((self.width * height / self.height).max(1), height)
+        }
+    }
+
+    /// Draw as big as fits on `target`, in the middle.
+    pub fn draw_fitted<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let area = target.bounding_box();
+        let (width, height) = (area.size.width as usize, area.size.height as usize);
+        if (width, height) == (self.width, self.height) {
+            let raw: ImageRaw<BinaryColor> = ImageRaw::new(self.data, self.width as u32);
+            return Image::new(&raw, area.top_left).draw(target);
+        }
+        let (w, h) = self.fitted(width, height);
+        let corner = area.top_left + Point::new(((width - w) / 2) as i32, ((height - h) / 2) as i32);
+        let pixels = (0..h).flat_map(move |y| {
+            (0..w).map(move |x| {
+                let on = self.get(x * self.width / w, y * self.height / h);
+                Pixel(corner + Point::new(x as i32, y as i32), BinaryColor::from(on))
+            })
+        });
+        target.draw_iter(pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+
+    #[test]
+    fn same_size_draws_as_is() {
+        let mut data = [0u8; 1024];
+        data[0] = 0x80;
+        data[1023] = 0x01;
+        let mut frame = Frame::new();
+        Picture::new(&data, 128, 64).draw_fitted(&mut frame).unwrap();
+        assert!(frame.get(0, 0));
+        assert!(frame.get(127, 63));
+        assert!(!frame.get(1, 0));
+    }
+
+    #[test]
+    fn landscape_shrinks_onto_a_strip() {
+        // left half lit
+        let mut data = [0u8; 1024];
+        for row in data.chunks_mut(16) {
+            row[..8].copy_from_slice(&[0xff; 8]);
+        }
+        let picture = Picture::new(&data, 128, 64);
+        assert_eq!(picture.fitted(128, 32), (64, 32));
+        let mut frame = Frame::with_height(32);
+        picture.draw_fitted(&mut frame).unwrap();
+        // 32 columns either side are left dark
+        assert!(!frame.get(31, 10));
+        assert!(frame.get(32, 10));
+        assert!(frame.get(63, 31));
+        assert!(!frame.get(64, 0));
+        assert!(!frame.get(96, 0));
+    }
+
+    #[test]
+    fn strips_sit_in_the_middle_of_a_full_panel() {
+        let mut data = [0u8; 512];
+        data[0] = 0x80;
+        let picture = Picture::new(&data, 128, 32);
+        assert_eq!(picture.fitted(128, 64), (128, 32));
+        let mut frame = Frame::new();
+        picture.draw_fitted(&mut frame).unwrap();
+        // centred, so it starts 16 rows down
+        assert!(!frame.get(0, 0));
+        assert!(frame.get(0, 16));
+        assert!(!frame.get(1, 16));
+    }
+}
@@ -0,0 +1,701 @@
+//! Serial control protocol for a running microaleph.
+//!
+//! Frames are COBS encoded and end with a zero byte. Inside a frame is the
+//! [`VERSION`] byte, a one byte tag, the arguments little endian, and a
+//! CRC-16/CCITT-FALSE of all that, also little endian. The host sends
+//! [`Command`]s and gets a [`Response`] back for each one. A frame from
+//! any other version is [`Error::Version`], whatever it says.
+//!
+//! ```text
+//! tag  command       args
+//! 0x01 status
+//! 0x02 goto          index: u16
+//! 0x03 next
+//! 0x04 pause         on: u8
+//! 0x05 interval      ms: u32
+//! 0x06 shuffle       on: u8
+//! 0x07 brightness    level: u8
+//! 0x08 mode          0 image, 1 text
+//! 0x09 auto          on: u8, follow the light sensor
+//! 0x0a curve         POINTS x (reading: u16, contrast: u8)
+//! 0x0b rotation      degrees: u16, clockwise, 0, 90, 180 or 270
+//!
+//! 0x10 upload begin  frames: u16, planes: u8, width: u8, height: u8
+//! 0x11 upload chunk  offset: u32, data: up to CHUNK bytes
+//! 0x12 upload commit crc32: u32
+//!
+//! 0x80 ok
+//! 0x81 error         code: u8
+//! 0x82 status        index: u16, len: u16, paused: u8, interval: u32,
+//!                    shuffle: u8, brightness: u8, mode: u8, auto: u8,
+//!                    rotation: u16
+//! ```
+
+use embedded_hal::serial;
+use heapless::Vec;
+
+use crate::brightness::{Curve, POINTS};
+use crate::rotation::Rotation;
+
+/// Bumped whenever a frame's layout changes, so both ends know they don't
+/// match rather than guessing from lengths. It starts clear of the tags,
+/// which is what frames from before versions began with.
+pub const VERSION: u8 = 0x20;
+/// Most gallery bytes carried by one upload chunk.
+pub const CHUNK: usize = 256;
+/// Largest frame body, tag and args, before the CRC. An upload chunk is
+/// the biggest thing we send.
+pub const MAX_PAYLOAD: usize = 1 + 4 + CHUNK;
+// version, payload and CRC
+const BODY: usize = 1 + MAX_PAYLOAD + 2;
+/// Largest encoded frame, including the terminating zero.
+pub const MAX_FRAME: usize = BODY + BODY / 254 + 2;
+
+pub type Payload = Vec<u8, MAX_PAYLOAD>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// frame longer than MAX_FRAME, dropped
+    Overflow,
+    /// bad COBS encoding
+    Framing,
+    /// checksum mismatch
+    Crc,
+    /// unknown tag
+    Unknown,
+    /// args too short or too long for the tag
+    Length,
+    /// well formed, but out of range for this device
+    Invalid,
+    /// upload chunk or commit out of order
+    Sequence,
+    /// erasing or programming flash failed
+    Flash,
+    /// a frame from another protocol version
+    Version,
+}
+
+impl Error {
+    fn code(self) -> u8 {
+        match self {
+            Error::Overflow => 1,
+            Error::Framing => 2,
+            Error::Crc => 3,
+            Error::Unknown => 4,
+            Error::Length => 5,
+            Error::Invalid => 6,
+            Error::Sequence => 7,
+            Error::Flash => 8,
+            Error::Version => 9,
+        }
+    }
+
+    fn from_code(code: u8) -> Self {
+        match code {
+            1 => Error::Overflow,
+            2 => Error::Framing,
+            3 => Error::Crc,
+            4 => Error::Unknown,
+            5 => Error::Length,
+            7 => Error::Sequence,
+            8 => Error::Flash,
+            9 => Error::Version,
+            _ => Error::Invalid,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    Image,
+    Text,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    Status,
+    Goto(u16),
+    Next,
+    Pause(bool),
+    Interval(u32),
+    Shuffle(bool),
+    Brightness(u8),
+    Mode(Mode),
+    Auto(bool),
+    Curve(Curve),
+    Rotation(Rotation),
+}
+
+/// Sending a new gallery, see `gallery::Uploader`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Upload<'a> {
+    Begin { frames: u16, planes: u8, width: u8, height: u8 },
+    Chunk { offset: u32, data: &'a [u8] },
+    Commit { crc: u32 },
+}
+
+/// Anything the host can send.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Request<'a> {
+    Control(Command),
+    Upload(Upload<'a>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Status {
+    pub index: u16,
+    pub len: u16,
+    pub paused: bool,
+    pub interval_ms: u32,
+    pub shuffle: bool,
+    pub brightness: u8,
+    pub mode: Mode,
+    pub auto: bool,
+    pub rotation: Rotation,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Response {
+    Ok,
+    Error(Error),
+    Status(Status),
+}
+
+/// CRC-32 (IEEE), the one zlib and friends use. Checks whole galleries.
+pub fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// CRC-16/CCITT-FALSE
+pub fn crc16(data: &[u8]) -> u16 {
+    crc16_of(data.iter().copied())
+}
+
+fn crc16_of(data: impl Iterator<Item = u8>) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for b in data {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// COBS encode the version, `payload` and their CRC into `out`, zero
+/// terminated. Returns the number of bytes written. `out` must hold
+/// MAX_FRAME bytes.
+pub fn encode(payload: &[u8], out: &mut [u8]) -> usize {
+    let body = || core::iter::once(VERSION).chain(payload.iter().copied());
+    let crc = crc16_of(body()).to_le_bytes();
+    stuff(body().chain(crc.iter().copied()), out)
+}
+
+// COBS, the other half of `unstuff`
+fn stuff(bytes: impl Iterator<Item = u8>, out: &mut [u8]) -> usize {
+    let mut code_at = 0;
+    let mut n = 1;
+    let mut code = 1u8;
+    for b in bytes {
+        if b == 0 {
+            out[code_at] = code;
+            code_at = n;
+            n += 1;
+            code = 1;
+        } else {
+            out[n] = b;
+            n += 1;
+            code += 1;
+            if code == 0xff {
+                out[code_at] = code;
+                code_at = n;
+                n += 1;
+                code = 1;
+            }
+        }
+    }
+    out[code_at] = code;
+    out[n] = 0;
+    n + 1
+}
+
+/// Frame `payload` and block until it's all written to `tx`.
+pub fn send<W: serial::Write<u8>>(tx: &mut W, payload: &[u8]) -> Result<(), W::Error> {
+    let mut frame = [0u8; MAX_FRAME];
+    let n = encode(payload, &mut frame);
+    for &b in &frame[..n] {
+        nb::block!(tx.write(b))?;
+    }
+    nb::block!(tx.flush())
+}
+
+/// Undo COBS in place, returning the decoded length.
+fn unstuff(buf: &mut [u8]) -> Result<usize, Error> {
+    let mut read = 0;
+    let mut write = 0;
+    while read < buf.len() {
+        let code = buf[read] as usize;
+        if code == 0 || read + code > buf.len() {
+            return Err(Error::Framing);
+        }
+        read += 1;
+        for _ in 1..code {
+            buf[write] = buf[read];
+            write += 1;
+            read += 1;
+        }
+        if code != 0xff && read < buf.len() {
+            buf[write] = 0;
+            write += 1;
+        }
+    }
+    Ok(write)
+}
+
+/// Collects bytes off the wire into checked payloads.
+pub struct Decoder {
+    buf: Vec<u8, MAX_FRAME>,
+    overflow: bool,
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Decoder {
+            buf: Vec::new(),
+            overflow: false,
+        }
+    }
+
+    /// Feed one received byte. Returns a payload, version and CRC
+    /// stripped, or an error once a frame is complete.
+    pub fn feed(&mut self, byte: u8) -> Option<Result<Payload, Error>> {
+        if byte != 0 {
+            if self.buf.push(byte).is_err() {
+                self.overflow = true;
+            }
+            return None;
+        }
+        let result = self.finish();
+        self.buf.clear();
+        self.overflow = false;
+        result
+    }
+
+    fn finish(&mut self) -> Option<Result<Payload, Error>> {
+        if self.overflow {
+            return Some(Err(Error::Overflow));
+        }
+        if self.buf.is_empty() {
+            // back to back zeros, nothing to report
+            return None;
+        }
+        let len = match unstuff(&mut self.buf) {
+            Ok(len) => len,
+            Err(e) => return Some(Err(e)),
+        };
+        if len < 4 {
+            return Some(Err(Error::Length));
+        }
+        let (body, crc) = self.buf[..len].split_at(len - 2);
+        if crc16(body).to_le_bytes() != [crc[0], crc[1]] {
+            return Some(Err(Error::Crc));
+        }
+        if body[0] != VERSION {
+            return Some(Err(Error::Version));
+        }
+        Some(Payload::from_slice(&body[1..]).map_err(|_| Error::Length))
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn arg_u16(args: &[u8]) -> Result<u16, Error> {
+    match args {
+        [a, b] => Ok(u16::from_le_bytes([*a, *b])),
+        _ => Err(Error::Length),
+    }
+}
+
+fn arg_u32(args: &[u8]) -> Result<u32, Error> {
+    match args {
+        [a, b, c, d] => Ok(u32::from_le_bytes([*a, *b, *c, *d])),
+        _ => Err(Error::Length),
+    }
+}
+
+fn arg_u8(args: &[u8]) -> Result<u8, Error> {
+    match args {
+        [a] => Ok(*a),
+        _ => Err(Error::Length),
+    }
+}
+
+fn arg_none(args: &[u8]) -> Result<(), Error> {
+    if args.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Length)
+    }
+}
+
+fn curve_from(args: &[u8]) -> Result<Curve, Error> {
+    if args.len() != POINTS * 3 {
+        return Err(Error::Length);
+    }
+    let mut curve = Curve::default();
+    for (p, a) in curve.points.iter_mut().zip(args.chunks(3)) {
+        *p = (arg_u16(&a[..2])?, a[2]);
+    }
+    Ok(curve)
+}
+
+fn mode_from(b: u8) -> Result<Mode, Error> {
+    match b {
+        0 => Ok(Mode::Image),
+        1 => Ok(Mode::Text),
+        _ => Err(Error::Invalid),
+    }
+}
+
+fn rotation_from(degrees: u16) -> Result<Rotation, Error> {
+    Rotation::from_degrees(degrees).ok_or(Error::Invalid)
+}
+
+fn mode_byte(mode: Mode) -> u8 {
+    match mode {
+        Mode::Image => 0,
+        Mode::Text => 1,
+    }
+}
+
+impl Command {
+    pub fn parse(payload: &[u8]) -> Result<Self, Error> {
+        let (tag, args) = payload.split_first().ok_or(Error::Length)?;
+        match tag {
+            0x01 => arg_none(args).map(|_| Command::Status),
+            0x02 => arg_u16(args).map(Command::Goto),
+            0x03 => arg_none(args).map(|_| Command::Next),
+            0x04 => arg_u8(args).map(|b| Command::Pause(b != 0)),
+            0x05 => arg_u32(args).map(Command::Interval),
+            0x06 => arg_u8(args).map(|b| Command::Shuffle(b != 0)),
+            0x07 => arg_u8(args).map(Command::Brightness),
+            0x08 => arg_u8(args).and_then(mode_from).map(Command::Mode),
+            0x09 => arg_u8(args).map(|b| Command::Auto(b != 0)),
+            0x0a => curve_from(args).map(Command::Curve),
+            0x0b => arg_u16(args).and_then(rotation_from).map(Command::Rotation),
+            _ => Err(Error::Unknown),
+        }
+    }
+
+    pub fn payload(&self) -> Payload {
+        let mut p = Payload::new();
+        // nothing here comes close to MAX_PAYLOAD
+        match *self {
+            Command::Status => p.push(0x01).ok(),
+            Command::Goto(i) => {
+                p.push(0x02).ok();
+                p.extend_from_slice(&i.to_le_bytes()).ok()
+            }
+            Command::Next => p.push(0x03).ok(),
+            Command::Pause(on) => p.extend_from_slice(&[0x04, on as u8]).ok(),
+            Command::Interval(ms) => {
+                p.push(0x05).ok();
+                p.extend_from_slice(&ms.to_le_bytes()).ok()
+            }
+            Command::Shuffle(on) => p.extend_from_slice(&[0x06, on as u8]).ok(),
+            Command::Brightness(b) => p.extend_from_slice(&[0x07, b]).ok(),
+            Command::Mode(m) => p.extend_from_slice(&[0x08, mode_byte(m)]).ok(),
+            Command::Auto(on) => p.extend_from_slice(&[0x09, on as u8]).ok(),
+            Command::Curve(c) => {
+                p.push(0x0a).ok();
+                for (reading, contrast) in c.points.iter() {
+                    p.extend_from_slice(&reading.to_le_bytes()).ok();
+                    p.push(*contrast).ok();
+                }
+                Some(())
+            }
+            Command::Rotation(r) => {
+                p.push(0x0b).ok();
+                p.extend_from_slice(&r.degrees().to_le_bytes()).ok()
+            }
+        };
+        p
+    }
+}
+
+impl<'a> Upload<'a> {
+    pub fn parse(payload: &'a [u8]) -> Result<Self, Error> {
+        let (tag, args) = payload.split_first().ok_or(Error::Length)?;
+        match tag {
+            0x10 if args.len() != 5 => Err(Error::Length),
+            0x10 => Ok(Upload::Begin {
+                frames: arg_u16(&args[..2])?,
+                planes: args[2],
+                width: args[3],
+                height: args[4],
+            }),
+            0x11 if args.len() < 4 || args.len() > 4 + CHUNK => Err(Error::Length),
+            0x11 => Ok(Upload::Chunk {
+                offset: arg_u32(&args[..4])?,
+                data: &args[4..],
+            }),
+            0x12 => arg_u32(args).map(|crc| Upload::Commit { crc }),
+            _ => Err(Error::Unknown),
+        }
+    }
+
+    pub fn payload(&self) -> Payload {
+        let mut p = Payload::new();
+        match *self {
+            Upload::Begin {
+                frames,
+                planes,
+                width,
+                height,
+            } => {
+                p.push(0x10).ok();
+                p.extend_from_slice(&frames.to_le_bytes()).ok();
+                p.extend_from_slice(&[planes, width, height]).ok()
+            }
+            Upload::Chunk { offset, data } => {
+                p.push(0x11).ok();
+                p.extend_from_slice(&offset.to_le_bytes()).ok();
+                p.extend_from_slice(&data[..data.len().min(CHUNK)]).ok()
+            }
+            Upload::Commit { crc } => {
+                p.push(0x12).ok();
+                p.extend_from_slice(&crc.to_le_bytes()).ok()
+            }
+        };
+        p
+    }
+}
+
+impl<'a> Request<'a> {
+    pub fn parse(payload: &'a [u8]) -> Result<Self, Error> {
+        match payload.first() {
+            Some(0x10..=0x1f) => Upload::parse(payload).map(Request::Upload),
+            _ => Command::parse(payload).map(Request::Control),
+        }
+    }
+}
+
+impl Response {
+    pub fn parse(payload: &[u8]) -> Result<Self, Error> {
+        let (tag, args) = payload.split_first().ok_or(Error::Length)?;
+        match tag {
+            0x80 => arg_none(args).map(|_| Response::Ok),
+            0x81 => arg_u8(args).map(|c| Response::Error(Error::from_code(c))),
+            0x82 => {
+                if args.len() != 15 {
+                    return Err(Error::Length);
+                }
+                Ok(Response::Status(Status {
+                    index: arg_u16(&args[0..2])?,
+                    len: arg_u16(&args[2..4])?,
+                    paused: args[4] != 0,
+                    interval_ms: arg_u32(&args[5..9])?,
+                    shuffle: args[9] != 0,
+                    brightness: args[10],
+                    mode: mode_from(args[11])?,
+                    auto: args[12] != 0,
+                    rotation: rotation_from(arg_u16(&args[13..15])?)?,
+                }))
+            }
+            _ => Err(Error::Unknown),
+        }
+    }
+
+    pub fn payload(&self) -> Payload {
+        let mut p = Payload::new();
+        match *self {
+            Response::Ok => p.push(0x80).ok(),
+            Response::Error(e) => p.extend_from_slice(&[0x81, e.code()]).ok(),
+            Response::Status(s) => {
+                p.push(0x82).ok();
+                p.extend_from_slice(&s.index.to_le_bytes()).ok();
+                p.extend_from_slice(&s.len.to_le_bytes()).ok();
+                p.push(s.paused as u8).ok();
+                p.extend_from_slice(&s.interval_ms.to_le_bytes()).ok();
+                p.push(s.shuffle as u8).ok();
+                p.push(s.brightness).ok();
+                p.push(mode_byte(s.mode)).ok();
+                p.push(s.auto as u8).ok();
+                p.extend_from_slice(&s.rotation.degrees().to_le_bytes()).ok()
+            }
+        };
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(payload: &[u8]) -> Vec<u8, MAX_FRAME> {
+        let mut out = [0u8; MAX_FRAME];
+        let n = encode(payload, &mut out);
+        Vec::from_slice(&out[..n]).unwrap()
+    }
+
+    fn decode_all(stream: &[u8]) -> Vec<Result<Payload, Error>, 16> {
+        let mut decoder = Decoder::new();
+        let mut found = Vec::new();
+        for &b in stream {
+            if let Some(r) = decoder.feed(b) {
+                found.push(r).unwrap();
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn crc_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29b1);
+    }
+
+    #[test]
+    fn commands_round_trip_through_a_byte_stream() {
+        let commands = [
+            Command::Status,
+            Command::Goto(513),
+            Command::Next,
+            Command::Pause(true),
+            Command::Interval(15_000),
+            Command::Shuffle(false),
+            Command::Brightness(0),
+            Command::Mode(Mode::Text),
+            Command::Auto(true),
+            Command::Curve(Curve::default()),
+            Command::Rotation(Rotation::Rotate270),
+        ];
+        let mut stream: Vec<u8, 256> = Vec::new();
+        for c in commands.iter() {
+            stream.extend_from_slice(&frame(&c.payload())).unwrap();
+        }
+        let found = decode_all(&stream);
+        assert_eq!(found.len(), commands.len());
+        for (c, r) in commands.iter().zip(found.iter()) {
+            let payload = r.as_ref().unwrap();
+            assert_eq!(Command::parse(payload), Ok(*c));
+        }
+    }
+
+    #[test]
+    fn status_round_trips() {
+        let status = Response::Status(Status {
+            index: 7,
+            len: 195,
+            paused: true,
+            interval_ms: 13_000,
+            shuffle: true,
+            brightness: 0x5f,
+            mode: Mode::Image,
+            auto: true,
+            rotation: Rotation::Rotate90,
+        });
+        let found = decode_all(&frame(&status.payload()));
+        assert_eq!(Response::parse(found[0].as_ref().unwrap()), Ok(status));
+
+        assert_eq!(Response::parse(&status.payload()[..14]), Err(Error::Length));
+        assert_eq!(Command::parse(&[0x0b, 45, 0]), Err(Error::Invalid));
+    }
+
+    #[test]
+    fn corrupt_frame_is_rejected_and_the_next_one_still_parses() {
+        let mut stream: Vec<u8, 64> = Vec::new();
+        let mut bad = frame(&Command::Goto(3).payload());
+        bad[2] ^= 0x40;
+        stream.extend_from_slice(&bad).unwrap();
+        stream.extend_from_slice(&frame(&Command::Next.payload())).unwrap();
+
+        let found = decode_all(&stream);
+        assert_eq!(found[0], Err(Error::Crc));
+        assert_eq!(Command::parse(found[1].as_ref().unwrap()), Ok(Command::Next));
+    }
+
+    #[test]
+    fn noise_and_overflow() {
+        let mut stream: Vec<u8, 512> = Vec::new();
+        // line noise before anything, then a run too long for a frame
+        stream.extend_from_slice(&[0, 0]).unwrap();
+        stream.extend_from_slice(&[0x55; MAX_FRAME + 1]).unwrap();
+        stream.push(0).unwrap();
+        stream.extend_from_slice(&frame(&Command::Status.payload())).unwrap();
+
+        let found = decode_all(&stream);
+        assert_eq!(found[0], Err(Error::Overflow));
+        assert_eq!(Command::parse(found[1].as_ref().unwrap()), Ok(Command::Status));
+    }
+
+    #[test]
+    fn upload_chunks_round_trip() {
+        let data = [0u8; CHUNK];
+        let chunk = Upload::Chunk { offset: 1024, data: &data };
+        let found = decode_all(&frame(&chunk.payload()));
+        let payload = found[0].as_ref().unwrap();
+        assert_eq!(Request::parse(payload), Ok(Request::Upload(chunk)));
+        assert_eq!(Request::parse(&[0x03]), Ok(Request::Control(Command::Next)));
+    }
+
+    #[test]
+    fn upload_begin_carries_the_size() {
+        let begin = Upload::Begin {
+            frames: 7,
+            planes: 1,
+            width: 128,
+            height: 32,
+        };
+        assert_eq!(Upload::parse(&begin.payload()), Ok(begin));
+        assert_eq!(Upload::parse(&[0x10, 7, 0, 2]), Err(Error::Length));
+        assert_eq!(Upload::parse(&[0x10, 7, 0, 2, 128]), Err(Error::Length));
+    }
+
+    #[test]
+    fn other_versions_are_turned_away() {
+        let body = [VERSION + 1, 0x03];
+        let crc = crc16(&body).to_le_bytes();
+        let mut stream = [0u8; 2 * MAX_FRAME];
+        let n = stuff(body.iter().chain(crc.iter()).copied(), &mut stream);
+        let n = n + encode(&Command::Next.payload(), &mut stream[n..]);
+
+        let found = decode_all(&stream[..n]);
+        assert_eq!(found[0], Err(Error::Version));
+        assert_eq!(Command::parse(found[1].as_ref().unwrap()), Ok(Command::Next));
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        // and it can be fed in pieces
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn bad_commands() {
+        assert_eq!(Command::parse(&[]), Err(Error::Length));
+        assert_eq!(Command::parse(&[0x42]), Err(Error::Unknown));
+        assert_eq!(Command::parse(&[0x02, 1]), Err(Error::Length));
+        assert_eq!(Command::parse(&[0x08, 9]), Err(Error::Invalid));
+        assert_eq!(Command::parse(&[0x0a, 0, 0, 1]), Err(Error::Length));
+    }
+}
@@ -0,0 +1,224 @@
+//! Keep going when a cheap OLED glitches.
+//!
+//! A NACK or a stuck bus shouldn't hang the board. [`Health`] counts
+//! consecutive display failures and tells the main loop whether to retry,
+//! to recover the bus and re-init the display, or to give up and let the
+//! watchdog reset the chip. [`clear_bus`] is the bus recovery part.
+
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+/// How hard to try before escalating.
+#[derive(Clone, Copy)]
+pub struct Policy {
+    /// plain retries before recovering the bus
+    pub retries: u8,
+    /// bus recoveries before giving up
+    pub recoveries: u8,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            retries: 3,
+            recoveries: 2,
+        }
+    }
+}
+
+/// What the main loop should do after a display operation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    /// it worked
+    Done,
+    /// try the same thing again
+    Retry,
+    /// free the bus, rebuild and re-init the display, then try again
+    Recover,
+    /// stop feeding the watchdog
+    GiveUp,
+}
+
+/// Consecutive failure count for one display.
+pub struct Health {
+    policy: Policy,
+    failures: u8,
+}
+
+impl Health {
+    pub fn new(policy: Policy) -> Self {
+        Health {
+            policy,
+            failures: 0,
+        }
+    }
+
+    /// Record the result of an init, draw or flush and decide what's next.
+    pub fn record<T, E>(&mut self, result: &Result<T, E>) -> Action {
+        if result.is_ok() {
+            self.failures = 0;
+            return Action::Done;
+        }
+        self.failures = self.failures.saturating_add(1);
+        let retries = self.policy.retries as u16 + 1;
+        let limit = retries * (self.policy.recoveries as u16 + 1);
+        let failures = self.failures as u16;
+        if failures >= limit {
+            Action::GiveUp
+        } else if failures.is_multiple_of(retries) {
+            Action::Recover
+        } else {
+            Action::Retry
+        }
+    }
+
+    /// Whether it's had to recover the bus since the last success, or
+    /// given up.
+    pub fn is_failing(&self) -> bool {
+        self.failures > self.policy.retries
+    }
+
+    /// True until the policy is exhausted. Only feed the watchdog while
+    /// this holds.
+    pub fn is_alive(&self) -> bool {
+        let retries = self.policy.retries as u16 + 1;
+        (self.failures as u16) < retries * (self.policy.recoveries as u16 + 1)
+    }
+}
+
+/// Run `f` up to `policy.retries + 1` times until it succeeds.
+pub fn retry<T, E>(policy: &Policy, mut f: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+    let mut result = f();
+    for _ in 0..policy.retries {
+        if result.is_ok() {
+            break;
+        }
+        result = f();
+    }
+    result
+}
+
+/// Free a bus whose slave is holding SDA low mid-byte.
+///
+/// The pins must be released from the I2C peripheral and configured as
+/// open drain outputs. SCL is clocked up to nine times until the slave lets
+/// go of SDA, then a STOP condition is generated. Returns whether SDA ended
+/// up high.
+pub fn clear_bus<SCL, SDA, D>(scl: &mut SCL, sda: &mut SDA, delay: &mut D) -> bool
+where
+    SCL: OutputPin,
+    SDA: OutputPin + InputPin,
+    D: DelayUs<u32>,
+{
+    // ~100kHz
+    const HALF: u32 = 5;
+
+    sda.set_high().ok();
+    scl.set_high().ok();
+    delay.delay_us(HALF);
+
+    for _ in 0..9 {
+        if sda.is_high().unwrap_or(false) {
+            break;
+        }
+        scl.set_low().ok();
+        delay.delay_us(HALF);
+        scl.set_high().ok();
+        delay.delay_us(HALF);
+    }
+
+    // STOP: SDA low to high while SCL is high
+    scl.set_low().ok();
+    delay.delay_us(HALF);
+    sda.set_low().ok();
+    delay.delay_us(HALF);
+    scl.set_high().ok();
+    delay.delay_us(HALF);
+    sda.set_high().ok();
+    delay.delay_us(HALF);
+
+    sda.is_high().unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAILED: Result<(), ()> = Err(());
+
+    #[test]
+    fn retries_then_recovers_then_gives_up() {
+        let mut health = Health::new(Policy::default());
+        let actions: [Action; 12] = core::array::from_fn(|_| health.record(&FAILED));
+        use Action::*;
+        assert_eq!(
+            actions,
+            [
+                Retry, Retry, Retry, Recover, Retry, Retry, Retry, Recover, Retry, Retry, Retry,
+                GiveUp
+            ]
+        );
+        // and stays given up
+        assert_eq!(health.record(&FAILED), GiveUp);
+    }
+
+    #[test]
+    fn success_starts_over() {
+        let mut health = Health::new(Policy::default());
+        for _ in 0..5 {
+            health.record(&FAILED);
+        }
+        assert!(health.is_failing());
+        assert_eq!(health.record(&Ok::<(), ()>(())), Action::Done);
+        assert!(!health.is_failing());
+        assert_eq!(health.record(&FAILED), Action::Retry);
+    }
+
+    #[test]
+    fn alive_until_it_gives_up() {
+        let policy = Policy {
+            retries: 1,
+            recoveries: 1,
+        };
+        let mut health = Health::new(policy);
+        assert!(health.is_alive());
+        assert_eq!(health.record(&FAILED), Action::Retry);
+        assert_eq!(health.record(&FAILED), Action::Recover);
+        assert_eq!(health.record(&FAILED), Action::Retry);
+        assert!(health.is_alive());
+        assert_eq!(health.record(&FAILED), Action::GiveUp);
+        assert!(!health.is_alive());
+    }
+
+    #[test]
+    fn no_retries_recovers_straight_away() {
+        let policy = Policy {
+            retries: 0,
+            recoveries: 1,
+        };
+        let mut health = Health::new(policy);
+        assert_eq!(health.record(&FAILED), Action::Recover);
+        assert_eq!(health.record(&FAILED), Action::GiveUp);
+    }
+
+    #[test]
+    fn retry_stops_at_the_first_success() {
+        let mut calls = 0;
+        let result = retry(&Policy::default(), || {
+            calls += 1;
+            if calls == 2 {
+                Ok(calls)
+            } else {
+                Err(())
+            }
+        });
+        assert_eq!((result, calls), (Ok(2), 2));
+
+        let mut calls = 0;
+        let result = retry(&Policy::default(), || {
+            calls += 1;
+            Err::<(), _>(calls)
+        });
+        assert_eq!(result, Err(4));
+    }
+}
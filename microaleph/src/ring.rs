@@ -0,0 +1,139 @@
+//! A ring of status LEDs, like the F3 Discovery's compass.
+//!
+//! [`Ring`] works out how bright each LED is from what the slideshow's
+//! doing: a light going round as it works through the gallery, blink codes
+//! when something's wrong, and a slow breathing glow while it's paused.
+//! The board dims them with PWM, [`mask`] says which are on at each step
+//! of it. LED 0 is at the top and the rest go round clockwise.
+
+/// LEDs in the ring.
+pub const LEDS: usize = 8;
+/// PWM steps, a level of `STEPS` is fully on.
+pub const STEPS: u8 = 16;
+
+/// Blink codes, how many blinks before each pause.
+pub mod code {
+    /// The panels' bus keeps failing and has been recovered.
+    pub const BUS: u8 = 2;
+}
+
+pub type Levels = [u8; LEDS];
+
+/// What the ring shows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Show {
+    /// Picture `index` of `len`, once round for the whole gallery.
+    Position { index: usize, len: usize },
+    /// A blink code from [`code`], on every LED.
+    Blink(u8),
+    Breathing,
+}
+
+// the tail behind the lit one, so it looks like it's moving
+const TAIL: u8 = STEPS / 4;
+const BLINK_MS: u32 = 200;
+const PAUSE_MS: u32 = 1000;
+const BREATH_MS: u32 = 4000;
+
+pub struct Ring {
+    ms: u32,
+}
+
+impl Ring {
+    pub fn new() -> Self {
+        Ring { ms: 0 }
+    }
+
+    /// `ms` went by. Returns how bright each LED is now.
+    pub fn tick(&mut self, ms: u32, show: Show) -> Levels {
+        self.ms = self.ms.wrapping_add(ms);
+        let mut levels = [0; LEDS];
+        match show {
+            Show::Position { index, len } => {
+                let lit = index * LEDS / len.max(1);
+                levels[lit] = STEPS;
+                levels[(lit + LEDS - 1) % LEDS] = TAIL;
+            }
+            Show::Blink(blinks) => {
+                let period = 2 * BLINK_MS * blinks as u32 + PAUSE_MS;
+                let t = self.ms % period;
+                if t < 2 * BLINK_MS * blinks as u32 && t % (2 * BLINK_MS) < BLINK_MS {
+                    levels = [STEPS; LEDS];
+                }
+            }
+            Show::Breathing => {
+                // a triangle, squared so it lingers near dark like breath
+                let t = self.ms % BREATH_MS;
+                let half = BREATH_MS / 2;
+                let up = if t < half { t } else { BREATH_MS - t };
+                let level = (up * up * STEPS as u32 / (half * half)) as u8;
+                levels = [level; LEDS];
+            }
+        }
+        levels
+    }
+}
+
+impl Default for Ring {
+    fn default() -> Self {
+        Ring::new()
+    }
+}
+
+/// Which LEDs are on at PWM step `step`, bit `i` for LED `i`. Each LED is
+/// on for `level` of every `STEPS` steps.
+pub fn mask(levels: &Levels, step: u8) -> u8 {
+    levels
+        .iter()
+        .enumerate()
+        .filter(|&(_, &level)| level > step)
+        .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_goes_round_once() {
+        let mut ring = Ring::new();
+        let levels = ring.tick(20, Show::Position { index: 0, len: 40 });
+        assert_eq!(levels, [STEPS, 0, 0, 0, 0, 0, 0, TAIL]);
+        let levels = ring.tick(20, Show::Position { index: 39, len: 40 });
+        assert_eq!(levels, [0, 0, 0, 0, 0, 0, TAIL, STEPS]);
+        let levels = ring.tick(20, Show::Position { index: 20, len: 40 });
+        assert_eq!(levels[4], STEPS);
+    }
+
+    #[test]
+    fn blink_codes_count_then_pause() {
+        let mut ring = Ring::new();
+        let mut on = 0;
+        let mut last = false;
+        // 20ms ticks up to where the next round of blinks starts
+        for _ in 1..(4 * BLINK_MS + PAUSE_MS) / 20 {
+            let lit = ring.tick(20, Show::Blink(code::BUS))[0] == STEPS;
+            if lit && !last {
+                on += 1;
+            }
+            last = lit;
+        }
+        assert_eq!(on, 2);
+    }
+
+    #[test]
+    fn breathing_rises_and_falls() {
+        let mut ring = Ring::new();
+        let start = ring.tick(0, Show::Breathing)[0];
+        let peak = ring.tick(BREATH_MS / 2, Show::Breathing)[0];
+        let end = ring.tick(BREATH_MS / 2, Show::Breathing)[0];
+        assert_eq!((start, peak, end), (0, STEPS, 0));
+    }
+
+    #[test]
+    fn pwm_is_on_for_its_level() {
+        let levels = [0, 1, STEPS / 2, STEPS, 0, 0, 0, 0];
+        let on = |i: usize| (0..STEPS).filter(|&s| mask(&levels, s) & 1 << i != 0).count();
+        assert_eq!((on(0), on(1), on(2), on(3)), (0, 1, 8, 16));
+    }
+}
@@ -0,0 +1,150 @@
+//! Which way up the panel is mounted.
+//!
+//! Rather than keeping pictures pre-rotated for every mounting, boards draw
+//! through [`Rotated`], which turns each pixel on its way to the panel.
+//! Pictures stay the right way up in storage, portrait ones taller than
+//! they're wide, and a turned panel just looks like a portrait one to
+//! whoever draws on it.
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+
+/// How far the picture is turned clockwise on the panel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rotation {
+    Rotate0,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    pub fn from_degrees(degrees: u16) -> Option<Self> {
+        match degrees {
+            0 => Some(Rotation::Rotate0),
+            90 => Some(Rotation::Rotate90),
+            180 => Some(Rotation::Rotate180),
+            270 => Some(Rotation::Rotate270),
+            _ => None,
+        }
+    }
+
+    pub fn degrees(self) -> u16 {
+        match self {
+            Rotation::Rotate0 => 0,
+            Rotation::Rotate90 => 90,
+            Rotation::Rotate180 => 180,
+            Rotation::Rotate270 => 270,
+        }
+    }
+
+    /// Turned by `self`, then by `other` on top.
+    pub fn plus(self, other: Rotation) -> Self {
+        Rotation::from_degrees((self.degrees() + other.degrees()) % 360).unwrap()
+    }
+
+    /// Whether width and height swap over.
+    pub fn is_turned(self) -> bool {
+        matches!(self, Rotation::Rotate90 | Rotation::Rotate270)
+    }
+
+    /// What a panel `size` looks like turned.
+    pub fn size(self, size: Size) -> Size {
+        if self.is_turned() {
+            Size::new(size.height, size.width)
+        } else {
+            size
+        }
+    }
+}
+
+/// Draws everything through it turned by `rotation`.
+pub struct Rotated<'a, D> {
+    target: &'a mut D,
+    rotation: Rotation,
+}
+
+impl<'a, D: DrawTarget<Color = BinaryColor>> Rotated<'a, D> {
+    pub fn new(target: &'a mut D, rotation: Rotation) -> Self {
+        Rotated { target, rotation }
+    }
+}
+
+impl<D: DrawTarget<Color = BinaryColor>> OriginDimensions for Rotated<'_, D> {
+    fn size(&self) -> Size {
+        self.rotation.size(self.target.bounding_box().size)
+    }
+}
+
+impl<D: DrawTarget<Color = BinaryColor>> DrawTarget for Rotated<'_, D> {
+    type Color = BinaryColor;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<BinaryColor>>,
+    {
+        let size = self.target.bounding_box().size;
+        let (right, bottom) = (size.width as i32 - 1, size.height as i32 - 1);
+        let rotation = self.rotation;
+        let turn = move |p: Point| match rotation {
+            Rotation::Rotate0 => p,
+            Rotation::Rotate90 => Point::new(right - p.y, p.x),
+            Rotation::Rotate180 => Point::new(right - p.x, bottom - p.y),
+            Rotation::Rotate270 => Point::new(p.y, bottom - p.x),
+        };
+        self.target
+            .draw_iter(pixels.into_iter().map(|Pixel(p, c)| Pixel(turn(p), c)))
+    }
+
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Self::Error> {
+        self.target.clear(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+
+    #[test]
+    fn degrees_round_trip() {
+        for &degrees in &[0, 90, 180, 270] {
+            assert_eq!(Rotation::from_degrees(degrees).unwrap().degrees(), degrees);
+        }
+        assert_eq!(Rotation::from_degrees(45), None);
+    }
+
+    #[test]
+    fn turns_add_up() {
+        assert_eq!(Rotation::Rotate90.plus(Rotation::Rotate180), Rotation::Rotate270);
+        assert_eq!(Rotation::Rotate270.plus(Rotation::Rotate180), Rotation::Rotate90);
+        assert_eq!(Rotation::Rotate0.plus(Rotation::Rotate0), Rotation::Rotate0);
+    }
+
+    #[test]
+    fn corners_go_clockwise() {
+        // the top left corner of the picture, wherever it ends up
+        let corners = [
+            (Rotation::Rotate0, (0, 0)),
+            (Rotation::Rotate90, (127, 0)),
+            (Rotation::Rotate180, (127, 63)),
+            (Rotation::Rotate270, (0, 63)),
+        ];
+        for &(rotation, (x, y)) in &corners {
+            let mut frame = Frame::new();
+            let mut turned = Rotated::new(&mut frame, rotation);
+            Pixel(Point::zero(), BinaryColor::On).draw(&mut turned).unwrap();
+            assert!(frame.get(x, y), "{:?}", rotation);
+        }
+    }
+
+    #[test]
+    fn turned_panels_look_portrait() {
+        let mut frame = Frame::new();
+        let mut turned = Rotated::new(&mut frame, Rotation::Rotate270);
+        assert_eq!(turned.size(), Size::new(64, 128));
+        // the bottom right of a portrait picture
+        Pixel(Point::new(63, 127), BinaryColor::On).draw(&mut turned).unwrap();
+        assert!(frame.get(127, 0));
+    }
+}
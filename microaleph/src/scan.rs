@@ -0,0 +1,296 @@
+//! Finding the panels on an I2C bus, and sharing the bus between them.
+//!
+//! SSD1306 modules answer at 0x3C, or 0x3D with the address jumper moved,
+//! so one bus takes two. More need a TCA9548A multiplexer at 0x70, which
+//! switches the bus through to any of eight channels, each of which can
+//! have its two panels. [`scan`] finds them all at boot and the board
+//! makes each one a lane of the slideshow, see `Slideshow::lane`.
+//!
+//! Each panel's [`Lane`] is a display interface on the shared bus that
+//! switches the multiplexer to its channel before writing. Anything else
+//! straight on the bus, an accelerometer say, gets a [`Device`].
+
+use core::cell::RefCell;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use heapless::Vec;
+
+/// Where SSD1306s answer.
+pub const ADDRESSES: [u8; 2] = [0x3c, 0x3d];
+/// The TCA9548A with its address pins low.
+pub const MUX: u8 = 0x70;
+pub const CHANNELS: u8 = 8;
+/// Most panels on one bus.
+pub const LANES: usize = 4;
+
+// Co = 0, D/C# = 0 and a NOP, which every controller ignores
+const PROBE: [u8; 2] = [0x00, 0xe3];
+// bytes per write, after the control byte
+const CHUNK: usize = 32;
+
+/// One panel on the bus.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Target {
+    pub address: u8,
+    /// the multiplexer channel, or none if it's straight on the bus
+    pub channel: Option<u8>,
+}
+
+impl Target {
+    /// A lone panel at the usual address, for when nothing answered.
+    pub const DEFAULT: Target = Target {
+        address: 0x3c,
+        channel: None,
+    };
+}
+
+pub type Targets = Vec<Target, LANES>;
+
+/// Whether anything acknowledges at `address`.
+pub fn probe<I: Write>(i2c: &mut I, address: u8) -> bool {
+    i2c.write(address, &PROBE).is_ok()
+}
+
+/// Switch the multiplexer to `channel`, or to nothing.
+pub fn select<I: Write>(i2c: &mut I, channel: Option<u8>) -> Result<(), I::Error> {
+    i2c.write(MUX, &[channel.map_or(0, |c| 1 << c)])
+}
+
+/// Every panel that answers, straight on the bus first, then through the
+/// multiplexer's channels in order. Stops at [`LANES`].
+pub fn scan<I: Write>(i2c: &mut I) -> Targets {
+    let mut found = Targets::new();
+    // turning the multiplexer off tells us it's there, and hides its
+    // channels from the first pass
+    let mux = select(i2c, None).is_ok();
+    let channels = if mux { 0..CHANNELS } else { 0..0 };
+    let passes = core::iter::once(None).chain(channels.map(Some));
+    for channel in passes {
+        if found.is_full() || (channel.is_some() && select(i2c, channel).is_err()) {
+            break;
+        }
+        for &address in ADDRESSES.iter() {
+            // whatever's straight on the bus answers on every channel too
+            let direct = found.iter().any(|t| t.channel.is_none() && t.address == address);
+            if (channel.is_none() || !direct) && probe(i2c, address) {
+                found.push(Target { address, channel }).ok();
+            }
+        }
+    }
+    if mux {
+        select(i2c, None).ok();
+    }
+    found
+}
+
+/// A panel's display interface on a bus it shares with the others. The
+/// bus is `None` while the board has it apart to recover it.
+pub struct Lane<'a, I> {
+    bus: &'a RefCell<Option<I>>,
+    target: Target,
+}
+
+impl<'a, I: Write> Lane<'a, I> {
+    pub fn new(bus: &'a RefCell<Option<I>>, target: Target) -> Self {
+        Lane { bus, target }
+    }
+
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// The bus it shares, say to take it apart and recover it.
+    pub fn bus(&self) -> &'a RefCell<Option<I>> {
+        self.bus
+    }
+
+    /// Switch the multiplexer over, if the panel is behind it. Done before
+    /// every write, so whoever else uses the bus doesn't have to care.
+    pub fn select(&self) -> Result<(), DisplayError> {
+        let mut bus = self.bus.borrow_mut();
+        let i2c = bus.as_mut().ok_or(DisplayError::BusWriteError)?;
+        match self.target.channel {
+            Some(_) => select(i2c, self.target.channel).map_err(|_| DisplayError::BusWriteError),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&mut self, control: u8, data: DataFormat<'_>) -> Result<(), DisplayError> {
+        let bytes = match data {
+            DataFormat::U8(bytes) => bytes,
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        };
+        self.select()?;
+        let mut bus = self.bus.borrow_mut();
+        let i2c = bus.as_mut().ok_or(DisplayError::BusWriteError)?;
+        let mut buf = [control; CHUNK + 1];
+        for chunk in bytes.chunks(CHUNK) {
+            buf[1..=chunk.len()].copy_from_slice(chunk);
+            i2c.write(self.target.address, &buf[..=chunk.len()])
+                .map_err(|_| DisplayError::BusWriteError)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: Write> WriteOnlyDataCommand for Lane<'_, I> {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.write(0x00, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.write(0x40, buf)
+    }
+}
+
+/// Any other chip straight on the panels' bus, sharing it the way the
+/// lanes do. Errors are `None` while the board has the bus apart to
+/// recover it.
+pub struct Device<'a, I> {
+    bus: &'a RefCell<Option<I>>,
+}
+
+impl<'a, I> Device<'a, I> {
+    pub fn new(bus: &'a RefCell<Option<I>>) -> Self {
+        Device { bus }
+    }
+}
+
+impl<I: Write> Write for Device<'_, I> {
+    type Error = Option<I::Error>;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        let mut bus = self.bus.borrow_mut();
+        bus.as_mut().ok_or(None)?.write(address, bytes).map_err(Some)
+    }
+}
+
+impl<I: WriteRead> WriteRead for Device<'_, I> {
+    type Error = Option<I::Error>;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), Self::Error> {
+        let mut bus = self.bus.borrow_mut();
+        bus.as_mut()
+            .ok_or(None)?
+            .write_read(address, bytes, buf)
+            .map_err(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A multiplexer, if fitted, and the panels on each of its channels
+    struct Bus {
+        mux: bool,
+        direct: Vec<u8, 2>,
+        behind: [Vec<u8, 2>; CHANNELS as usize],
+        channels: u8,
+        writes: Vec<(u8, u8), 64>,
+    }
+
+    impl Bus {
+        fn new(mux: bool, direct: &[u8]) -> Self {
+            Bus {
+                mux,
+                direct: Vec::from_slice(direct).unwrap(),
+                behind: Default::default(),
+                channels: 0,
+                writes: Vec::new(),
+            }
+        }
+    }
+
+    impl Write for Bus {
+        type Error = ();
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+            if address == MUX && self.mux {
+                self.channels = bytes[0];
+                return Ok(());
+            }
+            let listening = (0..CHANNELS)
+                .filter(|c| self.channels & (1 << c) != 0)
+                .any(|c| self.behind[c as usize].contains(&address));
+            if !self.direct.contains(&address) && !listening {
+                return Err(());
+            }
+            self.writes.push((address, self.channels)).ok();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn two_panels_straight_on_the_bus() {
+        let mut bus = Bus::new(false, &[0x3d, 0x3c]);
+        let found = scan(&mut bus);
+        assert_eq!(
+            &found[..],
+            &[
+                Target { address: 0x3c, channel: None },
+                Target { address: 0x3d, channel: None },
+            ]
+        );
+        assert!(scan(&mut Bus::new(false, &[])).is_empty());
+    }
+
+    #[test]
+    fn panels_behind_the_multiplexer() {
+        let mut bus = Bus::new(true, &[0x3d]);
+        bus.behind[2].push(0x3c).unwrap();
+        bus.behind[5].push(0x3c).unwrap();
+        bus.behind[6].push(0x3c).unwrap();
+        bus.behind[7].push(0x3c).unwrap();
+        let found = scan(&mut bus);
+        // four lanes at most, channel 7 misses out
+        assert_eq!(
+            &found[..],
+            &[
+                Target { address: 0x3d, channel: None },
+                Target { address: 0x3c, channel: Some(2) },
+                Target { address: 0x3c, channel: Some(5) },
+                Target { address: 0x3c, channel: Some(6) },
+            ]
+        );
+        // and it's left switched off
+        assert_eq!(bus.channels, 0);
+    }
+
+    #[test]
+    fn lanes_switch_channels_before_writing() {
+        let mut bus = Bus::new(true, &[]);
+        bus.behind[1].push(0x3c).unwrap();
+        bus.behind[3].push(0x3c).unwrap();
+        let bus = RefCell::new(Some(bus));
+        let mut one = Lane::new(&bus, Target { address: 0x3c, channel: Some(1) });
+        let mut three = Lane::new(&bus, Target { address: 0x3c, channel: Some(3) });
+        one.send_data(DataFormat::U8(&[0xff; 40])).unwrap();
+        three.send_commands(DataFormat::U8(&[0xaf])).unwrap();
+        one.send_commands(DataFormat::U8(&[0xae])).unwrap();
+        // 40 bytes of data is two writes
+        assert_eq!(
+            &bus.borrow().as_ref().unwrap().writes[..],
+            &[(0x3c, 0x02), (0x3c, 0x02), (0x3c, 0x08), (0x3c, 0x02)]
+        );
+
+        // nothing to write to while the board recovers the bus
+        let taken = bus.borrow_mut().take();
+        assert!(one.send_commands(DataFormat::U8(&[0xae])).is_err());
+        *bus.borrow_mut() = taken;
+        assert!(one.send_commands(DataFormat::U8(&[0xae])).is_ok());
+    }
+
+    #[test]
+    fn devices_share_the_bus_too() {
+        let bus = RefCell::new(Some(Bus::new(false, &[0x19])));
+        let mut device = Device::new(&bus);
+        assert_eq!(device.write(0x19, &[0x20, 0x47]), Ok(()));
+        assert_eq!(device.write(0x18, &[0x20, 0x47]), Err(Some(())));
+        let taken = bus.borrow_mut().take();
+        assert_eq!(device.write(0x19, &[0x20, 0x47]), Err(None));
+        *bus.borrow_mut() = taken;
+        assert_eq!(&bus.borrow().as_ref().unwrap().writes[..], &[(0x19, 0)]);
+    }
+}
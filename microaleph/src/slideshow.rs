@@ -0,0 +1,171 @@
+//! Which picture is up, and what the control protocol can change about it.
+
+use core::fmt::Write;
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Baseline, Text},
+};
+use heapless::String;
+
+use crate::brightness::Curve;
+use crate::protocol::{Command, Error, Mode, Response, Status};
+use crate::rotation::Rotation;
+
+pub struct Slideshow {
+    pub index: usize,
+    pub len: usize,
+    pub paused: bool,
+    pub interval_ms: u32,
+    pub shuffle: bool,
+    pub brightness: u8,
+    pub mode: Mode,
+    /// follow the light sensor through `curve` instead of `brightness`
+    pub auto: bool,
+    pub curve: Curve,
+    /// how the panel is mounted, boards draw through `rotation::Rotated`
+    pub rotation: Rotation,
+    rng: u32,
+}
+
+/// Don't let a typo flip pictures faster than the panel can flush.
+pub const MIN_INTERVAL_MS: u32 = 250;
+/// A minute is plenty, and it fits a 16 bit millisecond timer.
+pub const MAX_INTERVAL_MS: u32 = 60_000;
+
+impl Slideshow {
+    pub fn new(len: usize, index: usize, interval_ms: u32) -> Self {
+        Slideshow {
+            index,
+            len,
+            paused: false,
+            interval_ms,
+            shuffle: false,
+            brightness: 0xff,
+            mode: Mode::Image,
+            auto: false,
+            curve: Curve::default(),
+            rotation: Rotation::Rotate0,
+            rng: 0x2545_f491,
+        }
+    }
+
+    // xorshift32, good enough to pick the next picture
+    fn random(&mut self) -> u32 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        x
+    }
+
+    /// Move to the next picture, in order or shuffled.
+    pub fn next(&mut self) {
+        if self.shuffle && self.len > 1 {
+            // skip over the current one so shuffle always changes picture
+            let step = 1 + self.random() as usize % (self.len - 1);
+            self.index = (self.index + step) % self.len;
+        } else {
+            self.index = (self.index + 1) % self.len;
+        }
+    }
+
+    /// What `lane` of a wall of `lanes` panels shows: the current picture
+    /// on the first, then spread out over the rest so they all differ.
+    pub fn lane(&self, lane: usize, lanes: usize) -> usize {
+        (self.index + lane * self.len / lanes.max(1)) % self.len
+    }
+
+    /// The interval elapsed. Returns whether there's a new picture to draw.
+    pub fn tick(&mut self) -> bool {
+        if self.paused {
+            return false;
+        }
+        self.next();
+        true
+    }
+
+    pub fn status(&self) -> Status {
+        Status {
+            index: self.index as u16,
+            len: self.len as u16,
+            paused: self.paused,
+            interval_ms: self.interval_ms,
+            shuffle: self.shuffle,
+            brightness: self.brightness,
+            mode: self.mode,
+            auto: self.auto,
+            rotation: self.rotation,
+        }
+    }
+
+    /// Apply a command from the host and build the reply. The board still
+    /// has to act on the new state: redraw, restart its timer, set the
+    /// panel brightness.
+    pub fn apply(&mut self, command: Command) -> Response {
+        match command {
+            Command::Status => return Response::Status(self.status()),
+            Command::Goto(i) if i as usize >= self.len => {
+                return Response::Error(Error::Invalid)
+            }
+            Command::Goto(i) => self.index = i as usize,
+            Command::Next => self.next(),
+            Command::Pause(on) => self.paused = on,
+            Command::Interval(ms) if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&ms) => {
+                return Response::Error(Error::Invalid)
+            }
+            Command::Interval(ms) => self.interval_ms = ms,
+            Command::Shuffle(on) => self.shuffle = on,
+            Command::Brightness(b) => self.brightness = b,
+            Command::Mode(m) => self.mode = m,
+            Command::Auto(on) => self.auto = on,
+            Command::Curve(c) if !c.is_valid() => return Response::Error(Error::Invalid),
+            Command::Curve(c) => self.curve = c,
+            Command::Rotation(r) => self.rotation = r,
+        }
+        Response::Ok
+    }
+
+    /// What text mode shows: where we are and how we got here.
+    pub fn draw_status<D>(&self, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let mut s: String<64> = String::new();
+        write!(
+            s,
+            "{} / {}\n{} ms\n{}\n{}",
+            self.index + 1,
+            self.len,
+            self.interval_ms,
+            if self.shuffle { "shuffle" } else { "in order" },
+            if self.paused { "paused" } else { "playing" },
+        )
+        .ok();
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+        Text::with_baseline(&s, Point::zero(), style, Baseline::Top).draw(display)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::Vec;
+
+    #[test]
+    fn lanes_spread_over_the_gallery() {
+        let mut show = Slideshow::new(10, 8, 1000);
+        let lanes: Vec<usize, 4> = (0..4).map(|l| show.lane(l, 4)).collect();
+        assert_eq!(&lanes[..], &[8, 0, 3, 5]);
+        // and move on together
+        show.next();
+        assert_eq!(show.lane(1, 4), 1);
+        // more lanes than pictures is fine
+        let show = Slideshow::new(2, 0, 1000);
+        assert_eq!(show.lane(3, 4), 1);
+    }
+}
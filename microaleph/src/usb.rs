@@ -0,0 +1,94 @@
+//! The control protocol over USB CDC-ACM, for boards with a USB port.
+//!
+//! The board builds the HAL's `UsbBusAllocator` (it has to live in a
+//! `static`) and hands it to [`Link::new`]. After that the link looks like
+//! another serial port: [`Link::read`] gives one frame at a time, and
+//! [`Link::reply`] sends the response back.
+//!
+//! Nothing here is interrupt driven, so the main loop has to call
+//! [`Link::read`] every few milliseconds or the host gives up enumerating.
+
+use usb_device::bus::{UsbBus, UsbBusAllocator};
+use usb_device::device::{UsbDevice, UsbDeviceBuilder, UsbVidPid};
+use usb_device::UsbError;
+use usbd_serial::{SerialPort, USB_CLASS_CDC};
+
+use crate::protocol::{encode, Decoder, Error, Payload, Response, MAX_FRAME};
+
+// the shared VID/PID pair for CDC-ACM devices, see pid.codes
+const VID_PID: UsbVidPid = UsbVidPid(0x16c0, 0x27dd);
+
+// one full speed bulk packet
+const PACKET: usize = 64;
+
+pub struct Link<'a, B: UsbBus> {
+    device: UsbDevice<'a, B>,
+    serial: SerialPort<'a, B>,
+    decoder: Decoder,
+    buf: [u8; PACKET],
+    pos: usize,
+    len: usize,
+}
+
+impl<'a, B: UsbBus> Link<'a, B> {
+    pub fn new(bus: &'a UsbBusAllocator<B>) -> Self {
+        // the serial class has to be allocated before the device is built
+        let serial = SerialPort::new(bus);
+        let device = UsbDeviceBuilder::new(bus, VID_PID)
+            .manufacturer("bballant")
+            .product("microaleph")
+            .serial_number("0001")
+            .device_class(USB_CLASS_CDC)
+            .build();
+        Link {
+            device,
+            serial,
+            decoder: Decoder::new(),
+            buf: [0; PACKET],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    /// Service the bus, then return the next whole frame from the host, if
+    /// one is in. Call it in a loop until it returns `None`.
+    pub fn read(&mut self) -> Option<Result<Payload, Error>> {
+        loop {
+            if self.pos == self.len {
+                self.pos = 0;
+                self.len = 0;
+                if !self.device.poll(&mut [&mut self.serial]) {
+                    return None;
+                }
+                match self.serial.read(&mut self.buf) {
+                    Ok(n) if n > 0 => self.len = n,
+                    _ => return None,
+                }
+            }
+            let byte = self.buf[self.pos];
+            self.pos += 1;
+            if let Some(frame) = self.decoder.feed(byte) {
+                return Some(frame);
+            }
+        }
+    }
+
+    /// Send a response back to the host. If the host isn't reading, the
+    /// rest of the frame is dropped, it'll time out and ask again.
+    pub fn reply(&mut self, response: &Response) {
+        let mut frame = [0u8; MAX_FRAME];
+        let n = encode(&response.payload(), &mut frame);
+        let mut sent = 0;
+        while sent < n {
+            match self.serial.write(&frame[sent..n]) {
+                Ok(written) => sent += written,
+                Err(UsbError::WouldBlock) => {
+                    if !self.device.poll(&mut [&mut self.serial]) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    }
+}
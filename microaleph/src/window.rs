@@ -0,0 +1,207 @@
+//! Looking through the panel at a picture much bigger than it.
+//!
+//! A [`Window`] is a panel sized view somewhere on an oversized picture,
+//! say 512x256. Turning the board pans it, like looking through a hole at
+//! something behind, and when nobody's turned it for a while it drifts on
+//! its own, bouncing off the edges. Only the pixels under the window come
+//! out of the packed picture, see [`Window::draw`].
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+
+use crate::motion::Rates;
+use crate::picture::Picture;
+
+/// Every knob.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Config {
+    /// Pixels the window moves for a degree the board turns.
+    pub px_per_degree: u8,
+    /// Turning slower than this, in millidegrees a second, is a hand
+    /// holding it still rather than turning it.
+    pub still_mdps: u32,
+    /// How long it has to be still before it drifts.
+    pub still_ms: u32,
+    /// How fast it drifts across, in pixels a second. Down goes at half
+    /// that, so it doesn't go round the same path.
+    pub drift_px_s: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            px_per_degree: 2,
+            still_mdps: 3000,
+            still_ms: 3000,
+            drift_px_s: 4,
+        }
+    }
+}
+
+// positions are in 256ths of a pixel, so slow drifts and turns add up
+const FRACTION: i64 = 256;
+
+pub struct Window {
+    config: Config,
+    sheet: Size,
+    x: i64,
+    y: i64,
+    // which way the drift's going, +-1 each
+    dx: i64,
+    dy: i64,
+    still_ms: u32,
+}
+
+impl Window {
+    /// A window on a `sheet` sized picture, starting in the middle of it.
+    pub fn new(config: Config, sheet: Size) -> Self {
+        Window {
+            config,
+            sheet,
+            x: sheet.width as i64 * FRACTION / 2,
+            y: sheet.height as i64 * FRACTION / 2,
+            dx: 1,
+            dy: 1,
+            still_ms: 0,
+        }
+    }
+
+    /// The window's top left corner on the picture, for a `view` sized
+    /// window. The middle of it is where the window's at.
+    pub fn corner(&self, view: Size) -> Point {
+        let max = |sheet: u32, view: u32| sheet.saturating_sub(view) as i64;
+        let x = (self.x / FRACTION - view.width as i64 / 2)
+            .clamp(0, max(self.sheet.width, view.width));
+        let y = (self.y / FRACTION - view.height as i64 / 2)
+            .clamp(0, max(self.sheet.height, view.height));
+        Point::new(x as i32, y as i32)
+    }
+
+    /// `ms` went by, turning at `rates` if there's a gyro. Returns whether
+    /// a `view` sized window moved.
+    pub fn update(&mut self, rates: Option<Rates>, ms: u32, view: Size) -> bool {
+        let before = self.corner(view);
+        let turning = rates.filter(|r| {
+            let still = self.config.still_mdps as i32;
+            r.x.abs() > still || r.y.abs() > still
+        });
+        if let Some(rates) = turning {
+            self.still_ms = 0;
+            // tipping the right edge down looks further right, the top
+            // edge down further up
+            let scale = ms as i64 * self.config.px_per_degree as i64 * FRACTION;
+            let px = |mdps: i32| mdps as i64 * scale / 1_000_000;
+            self.x -= px(rates.y);
+            self.y -= px(rates.x);
+        } else {
+            self.still_ms = self.still_ms.saturating_add(ms);
+            if self.still_ms >= self.config.still_ms {
+                let step = self.config.drift_px_s as i64 * ms as i64 * FRACTION / 1000;
+                self.x += self.dx * step;
+                self.y += self.dy * step / 2;
+            }
+        }
+        self.bounce(view);
+        self.corner(view) != before
+    }
+
+    // keep the window on the picture, turning the drift round at the edges
+    fn bounce(&mut self, view: Size) {
+        let bounds = |sheet: u32, view: u32| {
+            let low = view as i64 * FRACTION / 2;
+            let high = (sheet.max(view) as i64 * FRACTION - low).max(low);
+            (low, high)
+        };
+        let (left, right) = bounds(self.sheet.width, view.width);
+        let (top, bottom) = bounds(self.sheet.height, view.height);
+        if self.x <= left || self.x >= right {
+            self.dx = if self.x <= left { 1 } else { -1 };
+            self.x = self.x.clamp(left, right);
+        }
+        if self.y <= top || self.y >= bottom {
+            self.dy = if self.y <= top { 1 } else { -1 };
+            self.y = self.y.clamp(top, bottom);
+        }
+    }
+
+    /// Draw what's under the window of `sheet` to fill `target`. Straight
+    /// from the packed rows, a pixel at a time, only those that show.
+    pub fn draw<D>(&self, sheet: &Picture, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let area = target.bounding_box();
+        let view = Size::new(
+            area.size.width.min(sheet.width as u32),
+            area.size.height.min(sheet.height as u32),
+        );
+        let corner = self.corner(view);
+        let (cx, cy) = (corner.x as usize, corner.y as usize);
+        let (w, h) = (view.width as usize, view.height as usize);
+        let colors = (0..h).flat_map(move |y| {
+            (0..w).map(move |x| BinaryColor::from(sheet.get(cx + x, cy + y)))
+        });
+        target.fill_contiguous(&Rectangle::new(area.top_left, view), colors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Frame;
+
+    const SHEET: Size = Size::new(512, 256);
+    const VIEW: Size = Size::new(128, 64);
+
+    #[test]
+    fn starts_in_the_middle() {
+        let window = Window::new(Config::default(), SHEET);
+        assert_eq!(window.corner(VIEW), Point::new(192, 96));
+    }
+
+    #[test]
+    fn turning_pans() {
+        let mut window = Window::new(Config::default(), SHEET);
+        // 12.5 degrees a second for a second, 2 pixels a degree
+        let right = Rates { x: 0, y: -12_500, z: 0 };
+        for _ in 0..50 {
+            window.update(Some(right), 20, VIEW);
+        }
+        assert_eq!(window.corner(VIEW), Point::new(217, 96));
+        // a hand holding it still doesn't move it, until it drifts
+        let shaky = Rates { x: 1000, y: -2000, z: 0 };
+        assert!(!window.update(Some(shaky), 20, VIEW));
+        assert_eq!(window.corner(VIEW), Point::new(217, 96));
+    }
+
+    #[test]
+    fn drifts_when_still_and_bounces() {
+        let mut window = Window::new(Config::default(), SHEET);
+        let mut moved = false;
+        for _ in 0..149 {
+            moved |= window.update(None, 20, VIEW);
+        }
+        assert!(!moved);
+        // then 4 pixels a second for long enough to hit the right edge
+        for _ in 0..(60 * 50) {
+            window.update(None, 20, VIEW);
+            let corner = window.corner(VIEW);
+            assert!((0..=384).contains(&corner.x) && (0..=192).contains(&corner.y));
+        }
+        assert_eq!(window.dx, -1);
+    }
+
+    #[test]
+    fn draws_only_the_window() {
+        // a sheet with its middle pixel set
+        let mut data = [0u8; 512 * 256 / 8];
+        let (x, y) = (256, 128);
+        data[(y * 512 + x) / 8] = 0x80 >> (x % 8);
+        let sheet = Picture::new(&data, 512, 256);
+        let window = Window::new(Config::default(), SHEET);
+        let mut frame = Frame::new();
+        window.draw(&sheet, &mut frame).unwrap();
+        assert!(frame.get(64, 32));
+        let lit = (0..128).flat_map(|x| (0..64).map(move |y| (x, y)));
+        assert_eq!(lit.filter(|&(x, y)| frame.get(x, y)).count(), 1);
+    }
+}
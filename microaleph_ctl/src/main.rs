@@ -0,0 +1,380 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use glob::glob;
+use microaleph::brightness::{self, Curve, POINTS};
+use microaleph::gallery::{MAX_PLANES, PLANE};
+use microaleph::protocol::{self, Command, Decoder, Mode, Response, Upload, CHUNK, MAX_FRAME};
+use microaleph::rotation::Rotation;
+use serialport::SerialPort;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::time::Duration;
+
+fn on_off(value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("expected on or off, got {}", value)),
+    }
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("not a number: {}", value))
+}
+
+/// WxH, as the panel the frames were made for.
+fn size(value: &str) -> Result<(usize, usize), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("expected WxH, got {}", value))?;
+    let (width, height) = (number(width)?, number(height)?);
+    if width == 0 || height == 0 || width % 8 != 0 || width * height / 8 > PLANE {
+        return Err(format!("{} won't fit, rows are whole bytes up to 128x64", value));
+    }
+    Ok((width, height))
+}
+
+/// READING:CONTRAST pairs, readings going up.
+fn curve<'a>(values: impl Iterator<Item = &'a str>) -> Result<Curve, String> {
+    let mut curve = Curve::default();
+    let mut n = 0;
+    for (point, value) in curve.points.iter_mut().zip(values) {
+        let (reading, contrast) = value
+            .split_once(':')
+            .ok_or_else(|| format!("expected READING:CONTRAST, got {}", value))?;
+        *point = (number(reading)?, number(contrast)?);
+        n += 1;
+    }
+    if n != POINTS {
+        return Err(format!("expected {} points", POINTS));
+    }
+    if !curve.is_valid() {
+        return Err(String::from("readings have to go up"));
+    }
+    Ok(curve)
+}
+
+/// Turn the subcommand into a protocol command.
+fn command(matches: &ArgMatches) -> Result<Command, String> {
+    let (name, sub) = matches.subcommand();
+    let arg = |n| sub.and_then(|s| s.value_of(n)).unwrap_or_default();
+    match name {
+        "status" => Ok(Command::Status),
+        "goto" => number(arg("N")).map(Command::Goto),
+        "next" => Ok(Command::Next),
+        "pause" => Ok(Command::Pause(true)),
+        "resume" => Ok(Command::Pause(false)),
+        "interval" => number(arg("MS")).map(Command::Interval),
+        "shuffle" => on_off(arg("ON_OFF")).map(Command::Shuffle),
+        "brightness" => number(arg("X")).map(Command::Brightness),
+        "auto" => on_off(arg("ON_OFF")).map(Command::Auto),
+        "curve" => curve(sub.and_then(|s| s.values_of("POINT")).unwrap_or_default())
+            .map(Command::Curve),
+        "mode" => match arg("MODE") {
+            "image" => Ok(Command::Mode(Mode::Image)),
+            "text" => Ok(Command::Mode(Mode::Text)),
+            m => Err(format!("expected image or text, got {}", m)),
+        },
+        "rotate" => number(arg("DEGREES")).and_then(|d| {
+            Rotation::from_degrees(d)
+                .map(Command::Rotation)
+                .ok_or_else(|| format!("expected 0, 90, 180 or 270, got {}", d))
+        }),
+        _ => Err(String::from("no command")),
+    }
+}
+
+/// Send one frame and wait for the answer.
+fn transact(port: &mut dyn SerialPort, payload: &[u8]) -> io::Result<Response> {
+    let mut frame = [0u8; MAX_FRAME];
+    let n = protocol::encode(payload, &mut frame);
+    port.write_all(&frame[..n])?;
+    receive(port)
+}
+
+fn expect_ok(response: io::Result<Response>) -> Result<(), String> {
+    match response {
+        Ok(Response::Ok) => Ok(()),
+        Ok(Response::Error(e)) => Err(format!("Device said {:?}", e)),
+        Ok(r) => Err(format!("Unexpected {:?}", r)),
+        Err(e) => Err(format!("No answer: {}", e)),
+    }
+}
+
+/// Concatenate the frames matching `pattern`, in name order. They're all
+/// .gray, one plane each, or all .gray2 from `--gray4`, two planes each,
+/// and all `width` x `height`. Returns the frames and the planes per frame.
+fn read_gallery(
+    pattern: &str,
+    (width, height): (usize, usize),
+) -> Result<(Vec<u8>, usize), String> {
+    let plane = width * height / 8;
+    let mut paths: Vec<_> = glob(pattern)
+        .map_err(|e| format!("Bad glob {}: {}", pattern, e))?
+        .flatten()
+        .collect();
+    paths.sort();
+    let mut gallery = Vec::new();
+    let mut size = 0;
+    for path in paths {
+        let bytes = fs::read(&path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+        if size == 0 {
+            size = bytes.len();
+        }
+        let planes = size / plane;
+        if bytes.len() != size || planes * plane != size || planes == 0 || planes > MAX_PLANES {
+            return Err(format!("{:?} is {} bytes, expected {}", path, bytes.len(), size));
+        }
+        gallery.extend_from_slice(&bytes);
+    }
+    if gallery.is_empty() {
+        return Err(format!("Nothing matches {}", pattern));
+    }
+    Ok((gallery, size / plane))
+}
+
+/// Replace the gallery on the device. It only switches over once the last
+/// chunk is in and checks out, so this is safe to interrupt.
+/// The device scales frames that don't match its panel.
+fn upload(
+    port: &mut dyn SerialPort,
+    gallery: &[u8],
+    planes: usize,
+    (width, height): (usize, usize),
+) -> Result<(), String> {
+    let size = planes * width * height / 8;
+    let frames = u16::try_from(gallery.len() / size)
+        .map_err(|_| format!("More than {} frames won't go in one upload", u16::MAX))?;
+    let too_big = |_| format!("{}x{} frames won't go in an upload", width, height);
+    let begin = Upload::Begin {
+        frames,
+        planes: planes as u8,
+        width: u8::try_from(width).map_err(too_big)?,
+        height: u8::try_from(height).map_err(too_big)?,
+    };
+    println!("Erasing for {} {}x{} frames.", frames, width, height);
+    expect_ok(transact(port, &begin.payload()))?;
+
+    let mut offset = 0;
+    for (i, frame) in gallery.chunks(size).enumerate() {
+        for data in frame.chunks(CHUNK) {
+            let chunk = Upload::Chunk {
+                offset: offset as u32,
+                data,
+            };
+            expect_ok(transact(port, &chunk.payload()))?;
+            offset += data.len();
+        }
+        print!("\rSent {} of {} frames.", i + 1, frames);
+        io::stdout().flush().ok();
+    }
+    println!();
+
+    let commit = Upload::Commit {
+        crc: protocol::crc32(0, gallery),
+    };
+    expect_ok(transact(port, &commit.payload()))
+}
+
+/// Read until a whole frame is in, or the port times out.
+fn receive(port: &mut dyn SerialPort) -> io::Result<Response> {
+    let mut decoder = Decoder::new();
+    let mut byte = [0u8; 1];
+    loop {
+        port.read_exact(&mut byte)?;
+        if let Some(frame) = decoder.feed(byte[0]) {
+            return frame
+                .and_then(|p| Response::parse(&p))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)));
+        }
+    }
+}
+
+fn main() {
+    let matches = App::new("Microaleph Control")
+        .version("zero")
+        .author("bballant")
+        .about("Control a running microaleph over serial.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("PORT")
+                .short("p")
+                .long("port")
+                .takes_value(true)
+                .default_value("/dev/ttyUSB0")
+                .help("Serial port, USB boards show up as /dev/ttyACM0"),
+        )
+        .arg(
+            Arg::with_name("BAUD")
+                .short("b")
+                .long("baud")
+                .takes_value(true)
+                .default_value("115200")
+                .help("Baud rate"),
+        )
+        .subcommand(SubCommand::with_name("status").about("Show what's up"))
+        .subcommand(
+            SubCommand::with_name("goto")
+                .about("Show picture N, counting from 0")
+                .arg(Arg::with_name("N").required(true)),
+        )
+        .subcommand(SubCommand::with_name("next").about("Show the next picture"))
+        .subcommand(SubCommand::with_name("pause").about("Stop flipping pictures"))
+        .subcommand(SubCommand::with_name("resume").about("Start flipping pictures again"))
+        .subcommand(
+            SubCommand::with_name("interval")
+                .about("Milliseconds between pictures")
+                .arg(Arg::with_name("MS").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("shuffle")
+                .about("Pick pictures at random")
+                .arg(Arg::with_name("ON_OFF").required(true).possible_values(&["on", "off"])),
+        )
+        .subcommand(
+            SubCommand::with_name("brightness")
+                .about("Panel contrast, 0 to 255, or up or down a level")
+                .arg(Arg::with_name("X").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("auto")
+                .about("Follow the light sensor")
+                .arg(Arg::with_name("ON_OFF").required(true).possible_values(&["on", "off"])),
+        )
+        .subcommand(
+            SubCommand::with_name("curve")
+                .about("Light sensor reading to contrast, kept across resets")
+                .arg(
+                    Arg::with_name("POINT")
+                        .required(true)
+                        .multiple(true)
+                        .number_of_values(POINTS as u64)
+                        .help("READING:CONTRAST, e.g. 0:1 300:16 1500:128 3500:255"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("mode")
+                .about("Show pictures or the settings as text")
+                .arg(Arg::with_name("MODE").required(true).possible_values(&["image", "text"])),
+        )
+        .subcommand(
+            SubCommand::with_name("rotate")
+                .about("How the panel is mounted, clockwise, kept across resets")
+                .arg(
+                    Arg::with_name("DEGREES")
+                        .required(true)
+                        .possible_values(&["0", "90", "180", "270"]),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("upload")
+                .about("Replace the gallery with .gray or .gray2 frames")
+                .arg(
+                    Arg::with_name("GLOB")
+                        .required(true)
+                        .help("Input glob, uploaded in name order"),
+                )
+                .arg(
+                    Arg::with_name("SIZE")
+                        .long("size")
+                        .takes_value(true)
+                        .default_value("128x64")
+                        .help("Frame size, e.g. 128x32 for frames made for the strips"),
+                ),
+        )
+        .get_matches();
+
+    // read the frames before touching the device
+    let gallery = matches.subcommand_matches("upload").map(|sub| {
+        size(sub.value_of("SIZE").unwrap())
+            .and_then(|size| Ok((read_gallery(sub.value_of("GLOB").unwrap(), size)?, size)))
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(2);
+            })
+    });
+    // brightness up or down, true for up
+    let step = match matches.subcommand_matches("brightness").and_then(|s| s.value_of("X")) {
+        Some("up") => Some(true),
+        Some("down") => Some(false),
+        _ => None,
+    };
+    let command = match gallery {
+        Some(_) => None,
+        None if step.is_some() => None,
+        None => Some(command(&matches).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        })),
+    };
+
+    let port_name = matches.value_of("PORT").unwrap();
+    let baud: u32 = number(matches.value_of("BAUD").unwrap()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    // erasing a flash slot can take a couple of seconds
+    let mut port = serialport::new(port_name, baud)
+        .timeout(Duration::from_secs(5))
+        .open()
+        .unwrap_or_else(|e| {
+            eprintln!("Could not open {}: {}", port_name, e);
+            process::exit(1);
+        });
+
+    // a leading zero flushes any half frame the device is holding
+    port.write_all(&[0]).unwrap_or_else(|e| {
+        eprintln!("Could not write to {}: {}", port_name, e);
+        process::exit(1);
+    });
+
+    let command = match (command, gallery, step) {
+        (Some(command), _, _) => command,
+        (None, Some(((gallery, planes), size)), _) => {
+            match upload(port.as_mut(), &gallery, planes, size) {
+                Ok(()) => {
+                    println!("Uploaded!");
+                    return;
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        // step from wherever the device is now
+        (None, None, Some(up)) => match transact(port.as_mut(), &Command::Status.payload()) {
+            Ok(Response::Status(s)) => Command::Brightness(brightness::step(s.brightness, up)),
+            _ => {
+                eprintln!("No status from {}", port_name);
+                process::exit(1);
+            }
+        },
+        (None, None, None) => unreachable!(),
+    };
+
+    match transact(port.as_mut(), &command.payload()) {
+        Ok(Response::Ok) => println!("ok"),
+        Ok(Response::Status(s)) => {
+            println!("picture    {} of {}", s.index + 1, s.len);
+            println!("paused     {}", s.paused);
+            println!("interval   {} ms", s.interval_ms);
+            println!("shuffle    {}", s.shuffle);
+            println!("brightness {}", s.brightness);
+            println!("mode       {:?}", s.mode);
+            println!("auto       {}", s.auto);
+            println!("rotation   {}", s.rotation.degrees());
+        }
+        Ok(Response::Error(e)) => {
+            eprintln!("Device said {:?}", e);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("No answer: {}", e);
+            process::exit(1);
+        }
+    }
+}
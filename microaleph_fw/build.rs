@@ -0,0 +1,35 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// each board-* feature and the memory layout that goes with it
+const BOARDS: [(&str, &str); 3] = [
+    ("CARGO_FEATURE_BOARD_F3DISCOVERY", "memory/f3discovery.x"),
+    ("CARGO_FEATURE_BOARD_BLACKPILL_F401", "memory/blackpill-f401.x"),
+    ("CARGO_FEATURE_BOARD_BLACKPILL_F411", "memory/blackpill-f411.x"),
+];
+
+fn main() {
+    // cortex-m-rt's link.x includes memory.x from the linker search path,
+    // so the board's one goes into OUT_DIR under that name. No board, or
+    // more than one, and src/board/mod.rs says so.
+    let picked: Vec<&str> = BOARDS
+        .iter()
+        .filter(|(feature, _)| env::var_os(feature).is_some())
+        .map(|&(_, memory)| memory)
+        .collect();
+    if let [memory] = picked[..] {
+        let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+        fs::copy(memory, out.join("memory.x")).unwrap();
+        println!("cargo:rustc-link-search={}", out.display());
+    }
+    for (_, memory) in BOARDS.iter() {
+        println!("cargo:rerun-if-changed={}", memory);
+    }
+
+    // defmt needs its linker script, but only when it's compiled in
+    if env::var_os("CARGO_FEATURE_DEFMT").is_some() {
+        println!("cargo:rustc-link-arg=-Tdefmt.x");
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,7 @@
+MEMORY
+{
+  /* The last two 128K sectors (6 and 7) hold uploaded galleries,
+     see src/board/blackpill_f411/flash.rs */
+  FLASH : ORIGIN = 0x08000000, LENGTH = 256K
+  RAM : ORIGIN = 0x20000000, LENGTH = 128K
+}
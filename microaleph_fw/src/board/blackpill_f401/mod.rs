@@ -0,0 +1,218 @@
+//! The WeAct Black Pill with an STM32F401CC. 256K of flash is no room for
+//! gallery slots, so it shows the built in pictures, with its panel on its
+//! side, and takes the control protocol over USB.
+//!
+//! LED PC13, button (KEY) PA0, both active low. The panels are in `panel`.
+
+use display_interface::DisplayError;
+use fugit::ExtU32;
+use microaleph::frame::Frame;
+use microaleph::protocol::{Error, Payload, Response};
+use microaleph::rotation::Rotation;
+use microaleph::usb::Link;
+use stm32f4xx_hal as hal;
+use hal::{
+    gpio,
+    otg_fs::{UsbBus, UsbBusType, USB},
+    pac,
+    prelude::*,
+    rcc::Clocks,
+    timer::{CounterMs, SysDelay},
+    watchdog::IndependentWatchdog,
+};
+use usb_device::bus::UsbBusAllocator;
+
+use super::{Board, NoFlash, Port, Timer};
+use crate::flush::Blocking;
+use crate::images::{self, Images};
+
+mod panel;
+
+use panel::Displays;
+
+static mut EP_MEMORY: [u32; 1024] = [0; 1024];
+
+pub struct BlackPillF401 {
+    displays: Displays,
+    flush: Blocking,
+    clocks: Clocks,
+    delay: SysDelay,
+    watchdog: IndependentWatchdog,
+    slide: CounterMs<pac::TIM2>,
+    second: CounterMs<pac::TIM4>,
+    fade: CounterMs<pac::TIM5>,
+    led: gpio::PC13<gpio::Output<gpio::PushPull>>,
+    button: gpio::PA0<gpio::Input>,
+    usb: Link<'static, UsbBusType>,
+}
+
+impl Board for BlackPillF401 {
+    type Flash = NoFlash;
+
+    const IMAGES: Images = images::PORTRAIT;
+    // mounted on its side, the pictures are portrait
+    const ROTATION: Rotation = Rotation::Rotate90;
+    const INTERVAL_MS: u32 = 1300;
+
+    fn take() -> (Self, Option<NoFlash>) {
+        let cp = cortex_m::Peripherals::take().unwrap();
+        let dp = pac::Peripherals::take().unwrap();
+        let rcc = dp.RCC.constrain();
+        // USB wants a 48MHz clock, the Black Pill has a 25MHz crystal
+        let clocks = rcc
+            .cfgr
+            .use_hse(25.MHz())
+            .sysclk(48.MHz())
+            .require_pll48clk()
+            .freeze();
+        // only SPI panels need it before the loop
+        #[allow(unused_mut)]
+        let mut delay = cp.SYST.delay(&clocks);
+
+        // Reset unless the main loop says it's healthy at least every 4 seconds
+        let mut watchdog = IndependentWatchdog::new(dp.IWDG);
+        watchdog.stop_on_debug(&dp.DBGMCU, true);
+        watchdog.start(4000.millis());
+
+        let gpioa = dp.GPIOA.split();
+        let gpiob = dp.GPIOB.split();
+        let gpioc = dp.GPIOC.split();
+        let mut led = gpioc.pc13.into_push_pull_output();
+        led.set_high();
+
+        // Control port over USB CDC-ACM, see microaleph::protocol
+        let usb = USB {
+            usb_global: dp.OTG_FS_GLOBAL,
+            usb_device: dp.OTG_FS_DEVICE,
+            usb_pwrclk: dp.OTG_FS_PWRCLK,
+            pin_dm: gpioa.pa11.into_alternate(),
+            pin_dp: gpioa.pa12.into_alternate(),
+            hclk: clocks.hclk(),
+        };
+        // take() runs once, so this is the only reference to EP_MEMORY
+        let usb_bus = cortex_m::singleton!(
+            : UsbBusAllocator<UsbBusType> =
+                UsbBus::new(usb, unsafe { &mut *core::ptr::addr_of_mut!(EP_MEMORY) })
+        )
+        .unwrap();
+
+        // The panels, on I2C1 or SPI1 depending on the interface feature
+        #[cfg(feature = "interface-i2c")]
+        let displays = panel::displays(
+            dp.I2C1,
+            gpiob.pb6.into_alternate_open_drain(),
+            gpiob.pb7.into_alternate_open_drain(),
+            &clocks,
+        );
+        #[cfg(feature = "interface-spi")]
+        let displays = panel::displays(
+            dp.SPI1,
+            gpioa.pa5.into_alternate(),
+            gpioa.pa7.into_alternate(),
+            gpioa.pa4.into_push_pull_output(),
+            gpiob.pb0.into_push_pull_output(),
+            gpiob.pb1.into_push_pull_output(),
+            &clocks,
+            &mut delay,
+        );
+
+        // timers rather than delays, USB needs polling every few ms
+        let mut slide = dp.TIM2.counter_ms(&clocks);
+        slide.start(Self::INTERVAL_MS.millis()).unwrap();
+        let mut second = dp.TIM4.counter_ms(&clocks);
+        second.start(1000.millis()).unwrap();
+        let mut fade = dp.TIM5.counter_ms(&clocks);
+        fade.start(20.millis()).unwrap();
+
+        let board = BlackPillF401 {
+            displays,
+            flush: Blocking::new(),
+            clocks,
+            delay,
+            watchdog,
+            slide,
+            second,
+            fade,
+            led,
+            button: gpioa.pa0.into_pull_up_input(),
+            usb: Link::new(usb_bus),
+        };
+        (board, None)
+    }
+
+    fn show_fault(frame: &Frame) {
+        let dp = unsafe { pac::Peripherals::steal() };
+        let rcc = dp.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        for display in panel::steal(&clocks).iter_mut() {
+            if display.init().is_ok() {
+                display.flush(frame, None).ok();
+            }
+        }
+    }
+
+    fn lanes(&self) -> usize {
+        self.displays.len()
+    }
+
+    fn init(&mut self, lane: usize) -> Result<(), DisplayError> {
+        self.flush.forget(lane);
+        self.displays[lane].init()
+    }
+
+    fn contrast(&mut self, lane: usize, contrast: u8) -> Result<(), DisplayError> {
+        self.displays[lane].contrast(contrast)
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.flush.frame()
+    }
+
+    fn flush(&mut self, lane: usize, _done: fn(bool)) -> Result<(), DisplayError> {
+        self.flush.flush(lane, &mut self.displays[lane])
+    }
+
+    fn recover(&mut self) {
+        panel::recover(&mut self.displays, &self.clocks, &mut self.delay);
+    }
+
+    fn interval(&mut self, ms: u32) {
+        self.slide.start(ms.millis()).unwrap();
+    }
+
+    fn elapsed(&mut self, timer: Timer) -> bool {
+        match timer {
+            Timer::Slide => self.slide.wait().is_ok(),
+            Timer::Second => self.second.wait().is_ok(),
+            Timer::Fade => self.fade.wait().is_ok(),
+        }
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.delay.delay_ms(ms);
+    }
+
+    fn led(&mut self, on: bool) {
+        if on {
+            self.led.set_low();
+        } else {
+            self.led.set_high();
+        }
+    }
+
+    fn button(&mut self) -> bool {
+        self.button.is_low()
+    }
+
+    fn request(&mut self) -> Option<(Port, Result<Payload, Error>)> {
+        self.usb.read().map(|frame| (Port::Usb, frame))
+    }
+
+    fn reply(&mut self, _port: Port, response: &Response) {
+        self.usb.reply(response);
+    }
+
+    fn feed(&mut self) {
+        self.watchdog.feed();
+    }
+}
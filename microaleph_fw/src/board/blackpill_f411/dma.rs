@@ -0,0 +1,266 @@
+//! Flushing a [`Frame`] to the panel on I2C2 by DMA.
+//!
+//! The display interface only does blocking writes, and the HAL has no I2C
+//! DMA yet, so this drives I2C2 and DMA1 stream 7 (channel 7 is I2C2_TX)
+//! at the register level. [`Display`] still does init and contrast, which
+//! are a few bytes each; this only takes over for the pixels.
+//!
+//! Only what changed since the last flush goes out, as planned by
+//! [`Transfers`]. [`Flush2::start`] returns as soon as the first write is
+//! going, and the DMA interrupt sends the STOP and starts the next one,
+//! calling the completion callback after the last. A NACK lands in the I2C
+//! error interrupt instead, which aborts and reports it.
+//!
+//! With a wall of panels each lane has its own idea of what's shown, and a
+//! flush goes to one lane at a time, switching the multiplexer first.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering};
+
+use cortex_m::interrupt::{free, Mutex};
+use cortex_m::peripheral::NVIC;
+use display_interface::DisplayError;
+use microaleph::frame::{Frame, Transfers};
+use microaleph::scan::LANES;
+use stm32f4xx_hal::pac::{self, interrupt};
+
+use super::panel::Display;
+use crate::panel::PANEL;
+
+const STREAM: usize = 7;
+// A byte at 400kHz is 22.5us, about 1100 cycles at 48MHz, and a spin reads
+// a register so takes a few. Every wait is bounded by the bytes it waits
+// on, which keeps the ones in the DMA interrupt short even on a stuck bus.
+const BYTE_SPINS: u32 = 500;
+
+// I2C
+const CR1_START: u32 = 1 << 8;
+const CR1_STOP: u32 = 1 << 9;
+const CR2_ITERREN: u32 = 1 << 8;
+const CR2_DMAEN: u32 = 1 << 11;
+const SR1_SB: u32 = 1 << 0;
+const SR1_ADDR: u32 = 1 << 1;
+const SR1_BTF: u32 = 1 << 2;
+const SR1_BERR: u32 = 1 << 8;
+const SR1_ARLO: u32 = 1 << 9;
+const SR1_AF: u32 = 1 << 10;
+const SR1_OVR: u32 = 1 << 11;
+const SR1_TIMEOUT: u32 = 1 << 14;
+// what raises the error interrupt here, there's no PEC or SMBus alert
+const SR1_ERRORS: u32 = SR1_BERR | SR1_ARLO | SR1_AF | SR1_OVR | SR1_TIMEOUT;
+const SR2_BUSY: u32 = 1 << 1;
+
+// DMA stream
+const S_EN: u32 = 1;
+const S_TEIE: u32 = 1 << 2;
+const S_TCIE: u32 = 1 << 4;
+const S_DIR_M2P: u32 = 0b01 << 6;
+const S_MINC: u32 = 1 << 10;
+const S_PL_HIGH: u32 = 0b10 << 16;
+const S_CHSEL_7: u32 = 7 << 25;
+// stream 7's flags in HISR and HIFCR
+const S7_FLAGS: u32 = 0b11_1101 << 22;
+const S7_TCIF: u32 = 1 << 27;
+
+static BUSY: AtomicBool = AtomicBool::new(false);
+// the lane's panel, set before each flush
+static ADDRESS: AtomicU8 = AtomicU8::new(0x3c);
+// a flush went wrong, so nobody knows what the panel is showing
+static LOST: AtomicBool = AtomicBool::new(false);
+// told whether the flush worked once it's over
+type Done = Option<fn(bool)>;
+static DONE: Mutex<Cell<Done>> = Mutex::new(Cell::new(None));
+// the plan being sent and how far along it is, only touched by the
+// interrupts while BUSY
+static PLAN: AtomicPtr<Transfers> = AtomicPtr::new(core::ptr::null_mut());
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+fn spin(bytes: u32, mut ready: impl FnMut() -> bool) -> Result<(), DisplayError> {
+    for _ in 0..bytes * BYTE_SPINS {
+        if ready() {
+            return Ok(());
+        }
+    }
+    Err(DisplayError::BusWriteError)
+}
+
+// Stop the stream, send the STOP and let go of the bus. Safe to call
+// whatever state the write got to.
+fn abort() {
+    let i2c = unsafe { &*pac::I2C2::ptr() };
+    let dma = unsafe { &*pac::DMA1::ptr() };
+    dma.st[STREAM].cr.modify(|r, w| unsafe { w.bits(r.bits() & !S_EN) });
+    dma.hifcr.write(|w| unsafe { w.bits(S7_FLAGS) });
+    i2c.cr1.modify(|r, w| unsafe { w.bits(r.bits() | CR1_STOP) });
+    i2c.cr2
+        .modify(|r, w| unsafe { w.bits(r.bits() & !(CR2_DMAEN | CR2_ITERREN)) });
+    // The errors clear by writing 0, the other flags ignore it. Any left
+    // set would go off again as soon as the next write enables ITERREN
+    i2c.sr1.modify(|r, w| unsafe { w.bits(r.bits() & !SR1_ERRORS) });
+}
+
+// DMA is done when the last byte goes into DR, not onto the wire, so
+// there's that and the one in the shift register still to go
+fn drained() -> bool {
+    let i2c = unsafe { &*pac::I2C2::ptr() };
+    spin(2, || i2c.sr1.read().bits() & (SR1_BTF | SR1_AF) != 0).is_ok()
+        && i2c.sr1.read().bits() & SR1_AF == 0
+}
+
+fn finish(ok: bool) {
+    abort();
+    // an error and the transfer complete can both go off, report once
+    if !BUSY.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    if !ok {
+        LOST.store(true, Ordering::SeqCst);
+    }
+    if let Some(done) = free(|cs| DONE.borrow(cs).get()) {
+        done(ok);
+    }
+}
+
+#[interrupt]
+fn DMA1_STREAM7() {
+    let dma = unsafe { &*pac::DMA1::ptr() };
+    if !BUSY.load(Ordering::SeqCst) || dma.hisr.read().bits() & S7_TCIF == 0 || !drained() {
+        finish(false);
+        return;
+    }
+    abort();
+    let next = NEXT.fetch_add(1, Ordering::SeqCst) + 1;
+    // start guarantees the plan outlives BUSY
+    let plan = unsafe { &*PLAN.load(Ordering::SeqCst) };
+    match plan.get(next) {
+        Some(bytes) => {
+            if begin(bytes).is_err() {
+                finish(false);
+            }
+        }
+        None => finish(true),
+    }
+}
+
+#[interrupt]
+fn I2C2_ER() {
+    finish(false);
+}
+
+// Address the panel and set DMA going on `bytes`.
+fn begin(bytes: &[u8]) -> Result<(), DisplayError> {
+    let i2c = unsafe { &*pac::I2C2::ptr() };
+    let dma = unsafe { &*pac::DMA1::ptr() };
+    let stream = &dma.st[STREAM];
+
+    stream.cr.write(|w| unsafe { w.bits(0) });
+    spin(1, || stream.cr.read().bits() & S_EN == 0)?;
+    dma.hifcr.write(|w| unsafe { w.bits(S7_FLAGS) });
+    stream.par.write(|w| unsafe { w.bits(&i2c.dr as *const _ as u32) });
+    stream.m0ar.write(|w| unsafe { w.bits(bytes.as_ptr() as u32) });
+    stream.ndtr.write(|w| unsafe { w.bits(bytes.len() as u32) });
+    stream.cr.write(|w| unsafe {
+        w.bits(S_CHSEL_7 | S_PL_HIGH | S_MINC | S_DIR_M2P | S_TCIE | S_TEIE | S_EN)
+    });
+
+    // the last write's STOP
+    spin(1, || i2c.sr2.read().bits() & SR2_BUSY == 0)?;
+    i2c.cr2.modify(|r, w| unsafe { w.bits(r.bits() | CR2_DMAEN) });
+    i2c.cr1.modify(|r, w| unsafe { w.bits(r.bits() | CR1_START) });
+    spin(1, || i2c.sr1.read().bits() & SR1_SB != 0)?;
+    let address = ADDRESS.load(Ordering::SeqCst) as u32;
+    i2c.dr.write(|w| unsafe { w.bits(address << 1) });
+    // the address and its ACK
+    spin(2, || i2c.sr1.read().bits() & (SR1_ADDR | SR1_AF) != 0)?;
+    if i2c.sr1.read().bits() & SR1_AF != 0 {
+        return Err(DisplayError::BusWriteError);
+    }
+    // reading SR2 after SR1 clears ADDR, and DMA takes it from there
+    let _ = i2c.sr2.read();
+    i2c.cr2.modify(|r, w| unsafe { w.bits(r.bits() | CR2_ITERREN) });
+    Ok(())
+}
+
+/// The frame to draw into, and what each lane's panel has from the last one.
+pub struct Flush2 {
+    frame: &'static mut Frame,
+    shown: &'static mut [Frame; LANES],
+    known: [bool; LANES],
+    // which lane LOST is about
+    sent: usize,
+    transfers: &'static mut Transfers,
+}
+
+impl Flush2 {
+    /// Only once, the buffers are statics.
+    pub fn take() -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.ahb1enr.modify(|_, w| w.dma1en().set_bit());
+        unsafe {
+            NVIC::unmask(pac::Interrupt::DMA1_STREAM7);
+            NVIC::unmask(pac::Interrupt::I2C2_ER);
+        }
+        Flush2 {
+            frame: cortex_m::singleton!(: Frame = PANEL.frame()).unwrap(),
+            shown: cortex_m::singleton!(: [Frame; LANES] = [(); LANES].map(|_| PANEL.frame()))
+                .unwrap(),
+            known: [false; LANES],
+            sent: 0,
+            transfers: cortex_m::singleton!(: Transfers = Transfers::new()).unwrap(),
+        }
+    }
+
+    pub fn busy(&self) -> bool {
+        BUSY.load(Ordering::SeqCst)
+    }
+
+    /// Free to draw into even while a flush is going out.
+    pub fn frame(&mut self) -> &mut Frame {
+        self.frame
+    }
+
+    /// Send everything to `lane` next time, say after an init.
+    pub fn forget(&mut self, lane: usize) {
+        self.known[lane] = false;
+    }
+
+    /// Send what changed to `lane` and return straight away. `done` gets
+    /// called from the interrupt with whether it all made it. The writes
+    /// go around `display`, it only says how to address the panel.
+    pub fn start(
+        &mut self,
+        lane: usize,
+        display: &mut Display,
+        done: fn(bool),
+    ) -> Result<(), DisplayError> {
+        if self.busy() {
+            return Err(DisplayError::BusWriteError);
+        }
+        if LOST.swap(false, Ordering::SeqCst) {
+            self.forget(self.sent);
+        }
+        // blocking, but it's one byte and only behind a multiplexer
+        display.interface().select()?;
+        ADDRESS.store(display.interface().target().address, Ordering::SeqCst);
+        let shown = if self.known[lane] { Some(&self.shown[lane]) } else { None };
+        self.transfers.plan(display.panel(), self.frame, shown);
+        self.shown[lane].clone_from(self.frame);
+        self.known[lane] = true;
+        self.sent = lane;
+        if self.transfers.is_empty() {
+            return Ok(());
+        }
+
+        PLAN.store(&mut *self.transfers, Ordering::SeqCst);
+        NEXT.store(0, Ordering::SeqCst);
+        free(|cs| DONE.borrow(cs).set(Some(done)));
+        BUSY.store(true, Ordering::SeqCst);
+        let started = begin(self.transfers.get(0).unwrap());
+        if started.is_err() {
+            abort();
+            BUSY.store(false, Ordering::SeqCst);
+            self.forget(lane);
+        }
+        started
+    }
+}
@@ -0,0 +1,42 @@
+//! The two gallery slots, flash sectors 6 and 7. memory.x keeps the
+//! firmware out of them.
+
+use microaleph::gallery::{Flash, Slot};
+use microaleph::protocol::Error;
+use stm32f4xx_hal::flash::{FlashExt, LockedFlash};
+
+// 128K sectors, offsets from the start of flash
+const SLOT_LEN: usize = 128 * 1024;
+
+fn sector(slot: Slot) -> (u8, usize) {
+    match slot {
+        Slot::A => (6, 0x4_0000),
+        Slot::B => (7, 0x6_0000),
+    }
+}
+
+pub struct GalleryFlash(pub LockedFlash);
+
+impl Flash for GalleryFlash {
+    fn capacity(&self) -> usize {
+        SLOT_LEN
+    }
+
+    fn erase(&mut self, slot: Slot) -> Result<(), Error> {
+        let (number, _) = sector(slot);
+        self.0.unlocked().erase(number).map_err(|_| Error::Flash)
+    }
+
+    fn program(&mut self, slot: Slot, offset: usize, data: &[u8]) -> Result<(), Error> {
+        let (_, start) = sector(slot);
+        self.0
+            .unlocked()
+            .program(start + offset, data.iter())
+            .map_err(|_| Error::Flash)
+    }
+
+    fn read(&self, slot: Slot) -> &[u8] {
+        let (_, start) = sector(slot);
+        &self.0.read()[start..start + SLOT_LEN]
+    }
+}
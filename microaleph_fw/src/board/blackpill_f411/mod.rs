@@ -0,0 +1,319 @@
+//! The WeAct Black Pill with an STM32F411CE, the board with everything:
+//! gallery uploads into flash, settings in the backup registers, a light
+//! sensor, the control protocol on USART1 and USB, and DMA for I2C panels.
+//!
+//! LED PC13, button (KEY) PA0, both active low. USART1 TX PA9, RX PA10.
+//! Light sensor: an LDR from 3V3 to PA1 and 10k from PA1 to ground.
+//! Without one, just leave auto brightness off. The panels are in
+//! `panel`.
+
+use display_interface::DisplayError;
+use fugit::ExtU32;
+use microaleph::frame::Frame;
+use microaleph::protocol::{self, Decoder, Error, Payload, Response};
+use microaleph::rotation::Rotation;
+use microaleph::slideshow::Slideshow;
+use microaleph::usb::Link;
+use stm32f4xx_hal as hal;
+use hal::{
+    adc::{config::{AdcConfig, SampleTime}, Adc},
+    flash::LockedFlash,
+    gpio,
+    otg_fs::{UsbBus, UsbBusType, USB},
+    pac,
+    prelude::*,
+    rcc::Clocks,
+    serial::{Config, Rx, Serial, Tx},
+    timer::{CounterMs, SysDelay},
+    watchdog::IndependentWatchdog,
+};
+use usb_device::bus::UsbBusAllocator;
+
+use super::{Board, Port, Timer};
+use crate::images::{self, Images};
+
+#[cfg(feature = "interface-i2c")]
+mod dma;
+mod flash;
+mod panel;
+mod settings;
+
+use flash::GalleryFlash;
+use panel::{Displays, Flush};
+use settings::Backup;
+
+static mut EP_MEMORY: [u32; 1024] = [0; 1024];
+
+pub struct BlackPillF411 {
+    displays: Displays,
+    flush: Flush,
+    clocks: Clocks,
+    delay: SysDelay,
+    watchdog: IndependentWatchdog,
+    slide: CounterMs<pac::TIM2>,
+    second: CounterMs<pac::TIM4>,
+    fade: CounterMs<pac::TIM5>,
+    // a 400kHz flush takes about 25ms
+    #[cfg(feature = "gray4")]
+    gray: CounterMs<pac::TIM10>,
+    led: gpio::PC13<gpio::Output<gpio::PushPull>>,
+    button: gpio::PA0<gpio::Input>,
+    ldr: gpio::PA1<gpio::Analog>,
+    adc1: Adc<pac::ADC1>,
+    tx1: Tx<pac::USART1>,
+    rx1: Rx<pac::USART1>,
+    decoder1: Decoder,
+    usb: Link<'static, UsbBusType>,
+    backup: Backup,
+}
+
+impl Board for BlackPillF411 {
+    type Flash = GalleryFlash;
+
+    const IMAGES: Images = images::LANDSCAPE;
+    const ROTATION: Rotation = Rotation::Rotate0;
+    const INTERVAL_MS: u32 = 13000;
+
+    fn take() -> (Self, Option<GalleryFlash>) {
+        let cp = cortex_m::Peripherals::take().unwrap();
+        let dp = pac::Peripherals::take().unwrap();
+        let backup = Backup::new(dp.RTC, dp.PWR, &dp.RCC);
+        let rcc = dp.RCC.constrain();
+        // USB wants a 48MHz clock, the Black Pill has a 25MHz crystal
+        let clocks = rcc
+            .cfgr
+            .use_hse(25.MHz())
+            .sysclk(48.MHz())
+            .require_pll48clk()
+            .freeze();
+        // only SPI panels need it before the loop
+        #[allow(unused_mut)]
+        let mut delay = cp.SYST.delay(&clocks);
+
+        // Reset unless the main loop says it's healthy at least every 8
+        // seconds. An upload erases a 128K sector, up to 4 seconds at x8,
+        // and a fast LSI can cut the timeout to two thirds of that
+        let mut watchdog = IndependentWatchdog::new(dp.IWDG);
+        watchdog.stop_on_debug(&dp.DBGMCU, true);
+        watchdog.start(8000.millis());
+
+        let gpioa = dp.GPIOA.split();
+        let gpiob = dp.GPIOB.split();
+        let gpioc = dp.GPIOC.split();
+        let mut led = gpioc.pc13.into_push_pull_output();
+        led.set_high();
+
+        // Control port on USART1, see microaleph::protocol
+        let serial1 = Serial::new(
+            dp.USART1,
+            (gpioa.pa9.into_alternate(), gpioa.pa10.into_alternate()),
+            Config::default().baudrate(115_200.bps()),
+            &clocks,
+        )
+        .unwrap();
+        let (tx1, mut rx1) = serial1.split();
+        // no handlers, but with SEVONPEND a byte coming in still wakes us
+        // from wfe
+        rx1.listen();
+        // SCR.SEVONPEND, which cortex-m 0.7 has no setter for
+        unsafe { cp.SCB.scr.modify(|r| r | 1 << 4) };
+
+        // The same protocol over USB CDC-ACM on the USB-C port
+        let usb = USB {
+            usb_global: dp.OTG_FS_GLOBAL,
+            usb_device: dp.OTG_FS_DEVICE,
+            usb_pwrclk: dp.OTG_FS_PWRCLK,
+            pin_dm: gpioa.pa11.into_alternate(),
+            pin_dp: gpioa.pa12.into_alternate(),
+            hclk: clocks.hclk(),
+        };
+        // take() runs once, so this is the only reference to EP_MEMORY
+        let usb_bus = cortex_m::singleton!(
+            : UsbBusAllocator<UsbBusType> =
+                UsbBus::new(usb, unsafe { &mut *core::ptr::addr_of_mut!(EP_MEMORY) })
+        )
+        .unwrap();
+
+        // The panels, on I2C2 or SPI1 depending on the interface feature.
+        // I2C finds however many there are.
+        #[cfg(feature = "interface-i2c")]
+        let displays = panel::displays(
+            dp.I2C2,
+            gpiob.pb10.into_alternate_open_drain(),
+            gpiob.pb3.into_alternate_open_drain(),
+            &clocks,
+        );
+        #[cfg(feature = "interface-spi")]
+        let displays = panel::displays(
+            dp.SPI1,
+            gpioa.pa5.into_alternate(),
+            gpioa.pa7.into_alternate(),
+            gpioa.pa4.into_push_pull_output(),
+            gpiob.pb0.into_push_pull_output(),
+            gpiob.pb1.into_push_pull_output(),
+            &clocks,
+            &mut delay,
+        );
+
+        let mut slide = dp.TIM2.counter_ms(&clocks);
+        slide.start(Self::INTERVAL_MS.millis()).unwrap();
+        let mut second = dp.TIM4.counter_ms(&clocks);
+        second.start(1000.millis()).unwrap();
+        let mut fade = dp.TIM5.counter_ms(&clocks);
+        fade.start(20.millis()).unwrap();
+        #[cfg(feature = "gray4")]
+        let mut gray = dp.TIM10.counter_ms(&clocks);
+        #[cfg(feature = "gray4")]
+        gray.start(25.millis()).unwrap();
+
+        // DMA for I2C, SPI flushes are over before DMA would've started
+        #[cfg(feature = "interface-i2c")]
+        let flush = Flush::take();
+        #[cfg(feature = "interface-spi")]
+        let flush = Flush::new();
+
+        let board = BlackPillF411 {
+            displays,
+            flush,
+            clocks,
+            delay,
+            watchdog,
+            slide,
+            second,
+            fade,
+            #[cfg(feature = "gray4")]
+            gray,
+            led,
+            button: gpioa.pa0.into_pull_up_input(),
+            ldr: gpioa.pa1.into_analog(),
+            adc1: Adc::adc1(dp.ADC1, true, AdcConfig::default()),
+            tx1,
+            rx1,
+            decoder1: Decoder::new(),
+            usb: Link::new(usb_bus),
+            backup,
+        };
+        (board, Some(GalleryFlash(LockedFlash::new(dp.FLASH))))
+    }
+
+    fn show_fault(frame: &Frame) {
+        let dp = unsafe { pac::Peripherals::steal() };
+        let rcc = dp.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        for display in panel::steal(&clocks).iter_mut() {
+            if display.init().is_ok() {
+                display.flush(frame, None).ok();
+            }
+        }
+    }
+
+    fn lanes(&self) -> usize {
+        self.displays.len()
+    }
+
+    fn init(&mut self, lane: usize) -> Result<(), DisplayError> {
+        self.flush.forget(lane);
+        self.displays[lane].init()
+    }
+
+    fn contrast(&mut self, lane: usize, contrast: u8) -> Result<(), DisplayError> {
+        self.displays[lane].contrast(contrast)
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.flush.frame()
+    }
+
+    #[cfg(feature = "interface-i2c")]
+    fn busy(&self) -> bool {
+        self.flush.busy()
+    }
+
+    // pixels go out by DMA, only the ones that changed
+    #[cfg(feature = "interface-i2c")]
+    fn flush(&mut self, lane: usize, done: fn(bool)) -> Result<(), DisplayError> {
+        self.flush.start(lane, &mut self.displays[lane], done)
+    }
+
+    #[cfg(feature = "interface-spi")]
+    fn flush(&mut self, lane: usize, _done: fn(bool)) -> Result<(), DisplayError> {
+        self.flush.flush(lane, &mut self.displays[lane])
+    }
+
+    fn recover(&mut self) {
+        panel::recover(&mut self.displays, &self.clocks, &mut self.delay);
+    }
+
+    fn interval(&mut self, ms: u32) {
+        self.slide.start(ms.millis()).unwrap();
+    }
+
+    fn elapsed(&mut self, timer: Timer) -> bool {
+        match timer {
+            Timer::Slide => self.slide.wait().is_ok(),
+            Timer::Second => self.second.wait().is_ok(),
+            Timer::Fade => self.fade.wait().is_ok(),
+            #[cfg(feature = "gray4")]
+            Timer::Gray => self.gray.wait().is_ok(),
+        }
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.delay.delay_ms(ms);
+    }
+
+    fn led(&mut self, on: bool) {
+        if on {
+            self.led.set_low();
+        } else {
+            self.led.set_high();
+        }
+    }
+
+    fn button(&mut self) -> bool {
+        self.button.is_low()
+    }
+
+    fn light(&mut self) -> Option<u16> {
+        Some(self.adc1.convert(&self.ldr, SampleTime::Cycles_480))
+    }
+
+    fn request(&mut self) -> Option<(Port, Result<Payload, Error>)> {
+        // bytes already in USART1 up to the end of a frame
+        while let Ok(byte) = self.rx1.read() {
+            if let Some(frame) = self.decoder1.feed(byte) {
+                return Some((Port::Serial, frame));
+            }
+        }
+        self.usb.read().map(|frame| (Port::Usb, frame))
+    }
+
+    fn reply(&mut self, port: Port, response: &Response) {
+        match port {
+            Port::Serial => {
+                protocol::send(&mut self.tx1, &response.payload()).ok();
+            }
+            Port::Usb => self.usb.reply(response),
+        }
+    }
+
+    fn load(&self, show: &mut Slideshow) {
+        self.backup.load(show);
+    }
+
+    fn save(&mut self, show: &Slideshow) {
+        self.backup.save(show);
+    }
+
+    fn feed(&mut self) {
+        self.watchdog.feed();
+    }
+
+    // The DMA interrupt wakes us, and so does the serial or USB port going
+    // pending
+    fn idle(&mut self) {
+        pac::NVIC::unpend(pac::Interrupt::USART1);
+        pac::NVIC::unpend(pac::Interrupt::OTG_FS);
+        cortex_m::asm::wfe();
+    }
+}
@@ -0,0 +1,167 @@
+//! Which bus the panels hang off, picked with the `interface-i2c` and
+//! `interface-spi` features. The board only sees [`Displays`], [`Flush`]
+//! and how to recover and steal them.
+//!
+//! I2C (4 pin modules): SCL PB10, SDA PB3. Whatever answers at 0x3C and
+//! 0x3D gets used, behind a TCA9548A as well, see `microaleph::scan`.
+//! Pixels go out by DMA, see `dma`.
+//!
+//! SPI (7 pin modules): SCK/D0 PA5, MOSI/D1 PA7, CS PA4, DC PB0, RES PB1.
+
+use stm32f4xx_hal as hal;
+use hal::{gpio, pac, prelude::*, rcc::Clocks, timer::SysDelay};
+
+use crate::panel;
+
+#[cfg(feature = "interface-i2c")]
+pub use i2c::*;
+#[cfg(feature = "interface-spi")]
+pub use spi::*;
+
+pub type Displays = panel::Displays<Interface>;
+
+#[cfg(feature = "interface-i2c")]
+mod i2c {
+    use super::*;
+    use core::cell::RefCell;
+    use hal::i2c::I2c;
+    use microaleph::oled::Oled;
+    use microaleph::recover;
+    use microaleph::scan::Lane;
+
+    pub use super::super::dma::Flush2 as Flush;
+
+    pub type Scl = gpio::PB10<gpio::Alternate<4, gpio::OpenDrain>>;
+    pub type Sda = gpio::PB3<gpio::Alternate<9, gpio::OpenDrain>>;
+    pub type Bus = I2c<pac::I2C2, (Scl, Sda)>;
+    pub type Shared = RefCell<Option<Bus>>;
+    pub type Interface = Lane<'static, Bus>;
+    // what dma.rs flushes
+    pub type Display = Oled<Interface>;
+
+    pub fn bus(i2c2: pac::I2C2, scl: Scl, sda: Sda, clocks: &Clocks) -> Bus {
+        // 400kHz is as fast as the F411's I2C goes, 1MHz needs an FMPI2C part.
+        // A flush takes about 25ms instead of 100ms.
+        I2c::new(
+            i2c2,
+            (scl, sda),
+            hal::i2c::Mode::fast(400.kHz(), hal::i2c::DutyCycle::Ratio2to1),
+            clocks,
+        )
+    }
+
+    /// Only once, the bus they share is a static.
+    pub fn displays(i2c2: pac::I2C2, scl: Scl, sda: Sda, clocks: &Clocks) -> Displays {
+        let shared = cortex_m::singleton!(: Shared = RefCell::new(None)).unwrap();
+        panel::lanes(bus(i2c2, scl, sda, clocks), shared)
+    }
+
+    // Take the bus back to its pins, clock a stuck slave off SDA and
+    // build it back up. The caller re-inits.
+    pub fn recover(displays: &mut Displays, clocks: &Clocks, delay: &mut SysDelay) {
+        microaleph::warn!("recovering I2C2");
+        let shared = displays[0].interface().bus();
+        let (i2c2, (scl, sda)) = shared.borrow_mut().take().unwrap().release();
+        let mut scl = scl.into_open_drain_output();
+        let mut sda = sda.into_open_drain_output();
+        if !recover::clear_bus(&mut scl, &mut sda, delay) {
+            microaleph::error!("SDA still held low");
+        }
+        let bus = bus(
+            i2c2,
+            scl.into_alternate_open_drain(),
+            sda.into_alternate_open_drain(),
+            clocks,
+        );
+        *shared.borrow_mut() = Some(bus);
+    }
+
+    /// For the fault handlers, whoever had the pins is gone.
+    pub fn steal(clocks: &Clocks) -> Displays {
+        let dp = unsafe { pac::Peripherals::steal() };
+        let gpiob = dp.GPIOB.split();
+        let bus = bus(
+            dp.I2C2,
+            gpiob.pb10.into_alternate_open_drain(),
+            gpiob.pb3.into_alternate_open_drain(),
+            clocks,
+        );
+        // its own, the one main had is still lent out
+        let shared = cortex_m::singleton!(: Shared = RefCell::new(None)).unwrap();
+        panel::lanes(bus, shared)
+    }
+}
+
+#[cfg(feature = "interface-spi")]
+mod spi {
+    use super::*;
+    use hal::spi::Spi;
+    use ssd1306::prelude::*;
+
+    pub use crate::flush::Blocking as Flush;
+
+    pub type Sck = gpio::PA5<gpio::Alternate<5>>;
+    pub type Mosi = gpio::PA7<gpio::Alternate<5>>;
+    pub type Cs = gpio::PA4<gpio::Output<gpio::PushPull>>;
+    pub type Dc = gpio::PB0<gpio::Output<gpio::PushPull>>;
+    pub type Res = gpio::PB1<gpio::Output<gpio::PushPull>>;
+    pub type Interface = SPIInterface<Spi<pac::SPI1, (Sck, gpio::NoPin, Mosi)>, Dc, Cs>;
+
+    fn interface(
+        spi1: pac::SPI1,
+        sck: Sck,
+        mosi: Mosi,
+        cs: Cs,
+        dc: Dc,
+        clocks: &Clocks,
+    ) -> Interface {
+        // the SSD1306 takes up to 10MHz, at 8MHz even a whole screen is
+        // about a millisecond, so there's nothing to gain from DMA
+        let spi1 = Spi::new(
+            spi1,
+            (sck, gpio::NoPin, mosi),
+            embedded_hal::spi::MODE_0,
+            8.MHz(),
+            clocks,
+        );
+        SPIInterface::new(spi1, dc, cs)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn displays(
+        spi1: pac::SPI1,
+        sck: Sck,
+        mosi: Mosi,
+        cs: Cs,
+        dc: Dc,
+        mut res: Res,
+        clocks: &Clocks,
+        delay: &mut SysDelay,
+    ) -> Displays {
+        // SPI modules want a reset pulse before init, I2C ones do it on their own
+        res.set_high();
+        delay.delay_ms(1u32);
+        res.set_low();
+        delay.delay_ms(10u32);
+        res.set_high();
+        panel::single(interface(spi1, sck, mosi, cs, dc, clocks))
+    }
+
+    // Nothing holds an SPI bus, a re-init is all it takes
+    pub fn recover(_displays: &mut Displays, _clocks: &Clocks, _delay: &mut SysDelay) {}
+
+    /// For the fault handlers, whoever had the pins is gone.
+    pub fn steal(clocks: &Clocks) -> Displays {
+        let dp = unsafe { pac::Peripherals::steal() };
+        let gpioa = dp.GPIOA.split();
+        let gpiob = dp.GPIOB.split();
+        panel::single(interface(
+            dp.SPI1,
+            gpioa.pa5.into_alternate(),
+            gpioa.pa7.into_alternate(),
+            gpioa.pa4.into_push_pull_output(),
+            gpiob.pb0.into_push_pull_output(),
+            clocks,
+        ))
+    }
+}
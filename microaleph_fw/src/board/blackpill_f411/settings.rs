@@ -0,0 +1,71 @@
+//! Brightness and rotation settings kept in the RTC backup registers.
+//!
+//! The flash is all firmware, pictures and gallery slots, so the settings
+//! live in the backup domain instead. That survives resets and the
+//! watchdog, but a power cut only with a coin cell on VBAT.
+
+use microaleph::brightness::{Curve, POINTS};
+use microaleph::rotation::Rotation;
+use microaleph::slideshow::Slideshow;
+use stm32f4xx_hal::pac;
+
+// "LITE"
+const MAGIC: u32 = 0x4554_494c;
+const AUTO: u32 = 1 << 8;
+// quarter turns, zero from before rotation
+const TURNS: u32 = 9;
+
+// register 0 magic, 1 rotation, auto and brightness, then the curve
+const FLAGS: usize = 1;
+const CURVE: usize = 2;
+
+pub struct Backup {
+    rtc: pac::RTC,
+}
+
+impl Backup {
+    /// Call before `RCC.constrain()`.
+    pub fn new(rtc: pac::RTC, pwr: pac::PWR, rcc: &pac::RCC) -> Self {
+        // the backup domain is write protected until DBP is set
+        rcc.apb1enr.modify(|_, w| w.pwren().set_bit());
+        pwr.cr.modify(|_, w| w.dbp().set_bit());
+        Backup { rtc }
+    }
+
+    fn read(&self, i: usize) -> u32 {
+        self.rtc.bkpr[i].read().bits()
+    }
+
+    fn write(&mut self, i: usize, value: u32) {
+        self.rtc.bkpr[i].write(|w| unsafe { w.bits(value) });
+    }
+
+    /// Put back what `save` stored, if anything was.
+    pub fn load(&self, show: &mut Slideshow) {
+        if self.read(0) != MAGIC {
+            return;
+        }
+        let mut words = [0; POINTS];
+        for (i, w) in words.iter_mut().enumerate() {
+            *w = self.read(CURVE + i);
+        }
+        if let Some(curve) = Curve::from_words(&words) {
+            let flags = self.read(FLAGS);
+            show.auto = flags & AUTO != 0;
+            show.brightness = flags as u8;
+            let degrees = ((flags >> TURNS) & 3) as u16 * 90;
+            show.rotation = Rotation::from_degrees(degrees).unwrap_or(Rotation::Rotate0);
+            show.curve = curve;
+        }
+    }
+
+    pub fn save(&mut self, show: &Slideshow) {
+        let turns = (show.rotation.degrees() / 90) as u32;
+        let flags = turns << TURNS | if show.auto { AUTO } else { 0 } | show.brightness as u32;
+        self.write(FLAGS, flags);
+        for (i, &w) in show.curve.to_words().iter().enumerate() {
+            self.write(CURVE + i, w);
+        }
+        self.write(0, MAGIC);
+    }
+}
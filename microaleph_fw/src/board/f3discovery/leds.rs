@@ -0,0 +1,107 @@
+//! The compass ring, LD3 to LD10 on PE8 to PE15, see `microaleph::ring`.
+//!
+//! TIM7 goes off every 250us and its interrupt steps the PWM, one write
+//! to GPIOE's BSRR a step, so the LEDs keep dimming evenly while the main
+//! loop waits on the panels.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use embedded_hal::timer::CountDown;
+use microaleph::ring::{self, Levels, LEDS, STEPS};
+use stm32f3xx_hal as hal;
+use hal::gpio::{gpioe, Input};
+use hal::pac::{self, interrupt};
+use hal::rcc::{Clocks, APB1};
+use hal::time::duration::Microseconds;
+use hal::timer::{self, Timer};
+
+// what main asked for, in ring order
+static LEVELS: [AtomicU8; LEDS] = [
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+    AtomicU8::new(0),
+];
+static STEP: AtomicU8 = AtomicU8::new(0);
+
+// LD3 north on PE9, then clockwise round to LD4 north west on PE8
+const PINS: [u8; LEDS] = [9, 10, 11, 12, 13, 14, 15, 8];
+
+/// PE8 to PE15, the rest of GPIOE goes elsewhere.
+pub struct Pins {
+    pub pe8: gpioe::PE8<Input>,
+    pub pe9: gpioe::PE9<Input>,
+    pub pe10: gpioe::PE10<Input>,
+    pub pe11: gpioe::PE11<Input>,
+    pub pe12: gpioe::PE12<Input>,
+    pub pe13: gpioe::PE13<Input>,
+    pub pe14: gpioe::PE14<Input>,
+    pub pe15: gpioe::PE15<Input>,
+}
+
+/// The timer that dims the ring. The pins are set up once and left to
+/// its interrupt.
+pub struct Leds {
+    _timer: Timer<pac::TIM7>,
+}
+
+impl Leds {
+    pub fn new(
+        pins: Pins,
+        moder: &mut gpioe::MODER,
+        otyper: &mut gpioe::OTYPER,
+        tim7: pac::TIM7,
+        clocks: Clocks,
+        apb1: &mut APB1,
+    ) -> Self {
+        // only ever driven through BSRR from here on
+        pins.pe8.into_push_pull_output(moder, otyper);
+        pins.pe9.into_push_pull_output(moder, otyper);
+        pins.pe10.into_push_pull_output(moder, otyper);
+        pins.pe11.into_push_pull_output(moder, otyper);
+        pins.pe12.into_push_pull_output(moder, otyper);
+        pins.pe13.into_push_pull_output(moder, otyper);
+        pins.pe14.into_push_pull_output(moder, otyper);
+        pins.pe15.into_push_pull_output(moder, otyper);
+
+        // 16 steps of 250us is a 250Hz PWM, too quick to see flicker
+        let mut timer = Timer::new(tim7, clocks, apb1);
+        timer.enable_interrupt(timer::Event::Update);
+        timer.start(Microseconds(250u32));
+        unsafe { pac::NVIC::unmask(pac::Interrupt::TIM7) };
+        Leds { _timer: timer }
+    }
+
+    pub fn set(&mut self, levels: &Levels) {
+        for (level, &l) in LEVELS.iter().zip(levels.iter()) {
+            level.store(l, Ordering::Relaxed);
+        }
+    }
+}
+
+#[interrupt]
+fn TIM7() {
+    // the update flag, or it goes straight off again
+    unsafe { (*pac::TIM7::ptr()).sr.write(|w| w.uif().clear_bit()) };
+    let step = STEP.load(Ordering::Relaxed);
+    STEP.store((step + 1) % STEPS, Ordering::Relaxed);
+
+    let mut levels = [0; LEDS];
+    for (l, level) in levels.iter_mut().zip(LEVELS.iter()) {
+        *l = level.load(Ordering::Relaxed);
+    }
+    let mask = ring::mask(&levels, step);
+    let (mut set, mut reset) = (0u32, 0u32);
+    for (i, &pin) in PINS.iter().enumerate() {
+        if mask & 1 << i != 0 {
+            set |= 1 << pin;
+        } else {
+            reset |= 1 << pin;
+        }
+    }
+    unsafe { (*pac::GPIOE::ptr()).bsrr.write(|w| w.bits(set | reset << 16)) };
+}
@@ -0,0 +1,269 @@
+//! The STM32F3DISCOVERY, with an STM32F303VC. No USB port for the control
+//! protocol yet and no room for gallery slots, so it shows the built in
+//! pictures and takes its cues from the button.
+//!
+//! The user button B1 on PA0 is active high and has its own pull-down.
+//! The compass ring of LEDs shows where the slideshow's up to, see
+//! `leds`, and the panels are in `panel`.
+//!
+//! With I2C panels, the LSM303DLHC's accelerometer shares I2C1 with them
+//! through shared-bus. It turns the pictures to match how the board's
+//! held, moves on a picture when it's shaken and pauses while it lies face
+//! down, see `microaleph::motion`. SPI panels leave I2C1 unused, and the
+//! board stays still.
+//!
+//! With the `window` feature, the L3GD20 gyro on SPI1 pans the window as
+//! the board's turned: SCK PA5, MISO PA6, MOSI PA7 and CS PE3, all wired
+//! on the board already.
+
+use display_interface::DisplayError;
+use embedded_hal::timer::CountDown;
+use microaleph::frame::Frame;
+#[cfg(feature = "interface-i2c")]
+use microaleph::motion::{self, Event, Lsm303dlhc, Motion};
+#[cfg(feature = "window")]
+use microaleph::motion::{L3gd20, Rates};
+use microaleph::ring::Levels;
+use microaleph::rotation::Rotation;
+use stm32f3xx_hal as hal;
+use hal::delay::Delay;
+use hal::gpio::{self, gpioa};
+#[cfg(feature = "window")]
+use hal::gpio::gpioe;
+use hal::pac;
+use hal::prelude::*;
+#[cfg(feature = "window")]
+use hal::spi::{self, Spi};
+use hal::time::duration::Milliseconds;
+use hal::timer;
+use hal::watchdog::IndependentWatchDog;
+
+use super::{Board, NoFlash, Timer};
+use crate::flush::Blocking;
+use crate::images::{self, Images};
+
+mod leds;
+mod panel;
+
+use leds::Leds;
+use panel::Displays;
+
+#[cfg(feature = "interface-i2c")]
+type Accelerometer = Lsm303dlhc<panel::Proxy>;
+#[cfg(feature = "window")]
+type GyroPins = (
+    gpioa::PA5<gpio::AF5<gpio::PushPull>>,
+    gpioa::PA6<gpio::AF5<gpio::PushPull>>,
+    gpioa::PA7<gpio::AF5<gpio::PushPull>>,
+);
+#[cfg(feature = "window")]
+type Gyro = L3gd20<Spi<pac::SPI1, GyroPins>, gpioe::PE3<gpio::Output<gpio::PushPull>>>;
+
+pub struct F3Discovery {
+    displays: Displays,
+    flush: Blocking,
+    delay: Delay,
+    watchdog: IndependentWatchDog,
+    slide: timer::Timer<pac::TIM2>,
+    second: timer::Timer<pac::TIM3>,
+    fade: timer::Timer<pac::TIM4>,
+    leds: Leds,
+    button: gpioa::PA0<gpio::Input>,
+    #[cfg(feature = "interface-i2c")]
+    accelerometer: Option<Accelerometer>,
+    #[cfg(feature = "interface-i2c")]
+    motion: Motion,
+    #[cfg(feature = "window")]
+    gyro: Option<Gyro>,
+}
+
+impl Board for F3Discovery {
+    type Flash = NoFlash;
+
+    const IMAGES: Images = images::LANDSCAPE;
+    const ROTATION: Rotation = Rotation::Rotate0;
+    const INTERVAL_MS: u32 = 6000;
+
+    fn take() -> (Self, Option<NoFlash>) {
+        let cp = cortex_m::Peripherals::take().unwrap();
+        let dp = pac::Peripherals::take().unwrap();
+        let mut flash = dp.FLASH.constrain();
+        let mut rcc = dp.RCC.constrain();
+        let clocks = rcc.cfgr.freeze(&mut flash.acr);
+        // only SPI panels need it before the loop
+        #[allow(unused_mut)]
+        let mut delay = Delay::new(cp.SYST, clocks);
+
+        // Reset unless the main loop says it's healthy at least every 10
+        // seconds, an image is up for about 6
+        let mut watchdog = IndependentWatchDog::new(dp.IWDG);
+        watchdog.stop_on_debug(&dp.DBGMCU, true);
+        watchdog.start(Milliseconds(10_000u32));
+
+        #[allow(unused_mut)]
+        let mut gpioa = dp.GPIOA.split(&mut rcc.ahb);
+        let gpiob = dp.GPIOB.split(&mut rcc.ahb);
+        let mut gpioe = dp.GPIOE.split(&mut rcc.ahb);
+
+        // The panels, on I2C1 or SPI2 depending on the interface feature.
+        // I2C finds however many there are.
+        #[cfg(feature = "interface-i2c")]
+        let (displays, i2c1) = panel::displays(dp.I2C1, gpiob, clocks, &mut rcc.apb1);
+        #[cfg(feature = "interface-spi")]
+        let displays = panel::displays(dp.SPI2, gpiob, clocks, &mut rcc.apb1, &mut delay);
+
+        // on the panels' bus, it just carries on if it doesn't answer
+        #[cfg(feature = "interface-i2c")]
+        let accelerometer = Lsm303dlhc::new(i2c1.acquire_i2c()).ok();
+        #[cfg(feature = "interface-i2c")]
+        if accelerometer.is_none() {
+            microaleph::warn!("no accelerometer");
+        }
+
+        let mut slide = timer::Timer::new(dp.TIM2, clocks, &mut rcc.apb1);
+        slide.start(Milliseconds(Self::INTERVAL_MS));
+        let mut second = timer::Timer::new(dp.TIM3, clocks, &mut rcc.apb1);
+        second.start(Milliseconds(1000u32));
+        let mut fade = timer::Timer::new(dp.TIM4, clocks, &mut rcc.apb1);
+        fade.start(Milliseconds(20u32));
+
+        // The gyro on SPI1, mode 3. 1MHz is well inside its 10MHz and a
+        // read is 7 bytes, so there's no hurry
+        #[cfg(feature = "window")]
+        let gyro = {
+            let (moder, otyper, afrl) = (&mut gpioa.moder, &mut gpioa.otyper, &mut gpioa.afrl);
+            let sck = gpioa.pa5.into_af_push_pull(moder, otyper, afrl);
+            let miso = gpioa.pa6.into_af_push_pull(moder, otyper, afrl);
+            let mosi = gpioa.pa7.into_af_push_pull(moder, otyper, afrl);
+            let mut cs = gpioe.pe3.into_push_pull_output(&mut gpioe.moder, &mut gpioe.otyper);
+            cs.set_high().ok();
+            let config = spi::config::Config::default()
+                .frequency(1.MHz())
+                .mode(embedded_hal::spi::MODE_3);
+            let spi = Spi::new(dp.SPI1, (sck, miso, mosi), config, clocks, &mut rcc.apb2);
+            L3gd20::new(spi, cs)
+        };
+        #[cfg(feature = "window")]
+        if gyro.is_none() {
+            microaleph::warn!("no gyro, drifting");
+        }
+
+        let pins = leds::Pins {
+            pe8: gpioe.pe8,
+            pe9: gpioe.pe9,
+            pe10: gpioe.pe10,
+            pe11: gpioe.pe11,
+            pe12: gpioe.pe12,
+            pe13: gpioe.pe13,
+            pe14: gpioe.pe14,
+            pe15: gpioe.pe15,
+        };
+        let leds = Leds::new(
+            pins,
+            &mut gpioe.moder,
+            &mut gpioe.otyper,
+            dp.TIM7,
+            clocks,
+            &mut rcc.apb1,
+        );
+
+        let board = F3Discovery {
+            displays,
+            flush: Blocking::new(),
+            delay,
+            watchdog,
+            slide,
+            second,
+            fade,
+            leds,
+            button: gpioa.pa0,
+            #[cfg(feature = "interface-i2c")]
+            accelerometer,
+            #[cfg(feature = "interface-i2c")]
+            motion: Motion::new(motion::Config::default()),
+            #[cfg(feature = "window")]
+            gyro,
+        };
+        (board, None)
+    }
+
+    fn show_fault(frame: &Frame) {
+        let dp = unsafe { pac::Peripherals::steal() };
+        let mut flash = dp.FLASH.constrain();
+        let rcc = dp.RCC.constrain();
+        let clocks = rcc.cfgr.freeze(&mut flash.acr);
+        for display in panel::steal(clocks).iter_mut() {
+            if display.init().is_ok() {
+                display.flush(frame, None).ok();
+            }
+        }
+    }
+
+    fn lanes(&self) -> usize {
+        self.displays.len()
+    }
+
+    fn init(&mut self, lane: usize) -> Result<(), DisplayError> {
+        self.flush.forget(lane);
+        self.displays[lane].init()
+    }
+
+    fn contrast(&mut self, lane: usize, contrast: u8) -> Result<(), DisplayError> {
+        self.displays[lane].contrast(contrast)
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.flush.frame()
+    }
+
+    fn flush(&mut self, lane: usize, _done: fn(bool)) -> Result<(), DisplayError> {
+        self.flush.flush(lane, &mut self.displays[lane])
+    }
+
+    fn recover(&mut self) {
+        panel::recover(&mut self.delay);
+    }
+
+    fn interval(&mut self, ms: u32) {
+        self.slide.start(Milliseconds(ms));
+    }
+
+    fn elapsed(&mut self, timer: Timer) -> bool {
+        match timer {
+            Timer::Slide => self.slide.wait().is_ok(),
+            Timer::Second => self.second.wait().is_ok(),
+            Timer::Fade => self.fade.wait().is_ok(),
+        }
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.delay.delay_ms(ms);
+    }
+
+    // LD3 is part of the ring, which says more
+    fn led(&mut self, _on: bool) {}
+
+    fn button(&mut self) -> bool {
+        self.button.is_high().unwrap_or(false)
+    }
+
+    fn ring(&mut self, levels: &Levels) {
+        self.leds.set(levels);
+    }
+
+    // 50 samples a second, the Fade timer's rate
+    #[cfg(feature = "interface-i2c")]
+    fn motion(&mut self) -> Option<Event> {
+        let sample = self.accelerometer.as_mut()?.read().ok()?;
+        self.motion.update(sample)
+    }
+
+    #[cfg(feature = "window")]
+    fn gyro(&mut self) -> Option<Rates> {
+        self.gyro.as_mut()?.read().ok()
+    }
+
+    fn feed(&mut self) {
+        self.watchdog.feed();
+    }
+}
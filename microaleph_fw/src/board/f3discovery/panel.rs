@@ -0,0 +1,227 @@
+//! Which bus the panels hang off, picked with the `interface-i2c` and
+//! `interface-spi` features. The board only sees [`Displays`] and how to
+//! recover and steal them.
+//!
+//! I2C (4 pin modules): SCL PB6, SDA PB7, shared with the LSM303DLHC
+//! through a shared-bus [`Manager`]. Whatever answers at 0x3C and 0x3D
+//! gets used, behind a TCA9548A as well, see `microaleph::scan`.
+//!
+//! SPI (7 pin modules): SPI2, since SPI1 is wired to the L3GD20.
+//! SCK/D0 PB13, MOSI/D1 PB15, CS PB12, DC PB11, RES PB10. PB14 is MISO,
+//! which the panel doesn't use, so leave it free.
+
+use stm32f3xx_hal as hal;
+use hal::delay::Delay;
+use hal::gpio::{self, gpiob};
+use hal::pac;
+use hal::prelude::*;
+use hal::rcc::{Clocks, APB1};
+
+use crate::panel;
+
+#[cfg(feature = "interface-i2c")]
+pub use i2c::*;
+#[cfg(feature = "interface-spi")]
+pub use spi::*;
+
+pub type Displays = panel::Displays<Interface>;
+
+#[cfg(feature = "interface-i2c")]
+mod i2c {
+    use super::*;
+    use core::cell::RefCell;
+    use core::convert::{Infallible, TryInto};
+    use embedded_hal::digital::v2::{InputPin, OutputPin};
+    use hal::i2c::I2c;
+    use microaleph::recover;
+    use microaleph::scan::Lane;
+    use shared_bus::{BusManagerSimple, I2cProxy, NullMutex};
+
+    pub type Scl = gpiob::PB6<gpio::AF4<gpio::OpenDrain>>;
+    pub type Sda = gpiob::PB7<gpio::AF4<gpio::OpenDrain>>;
+    pub type Bus = I2c<pac::I2C1, (Scl, Sda)>;
+    /// Has I2C1 for good and hands out a [`Proxy`] to each chip on it.
+    pub type Manager = BusManagerSimple<Bus>;
+    pub type Proxy = I2cProxy<'static, NullMutex<Bus>>;
+    pub type Shared = RefCell<Option<Proxy>>;
+    pub type Interface = Lane<'static, Proxy>;
+
+    fn bus(i2c1: pac::I2C1, scl: Scl, sda: Sda, clocks: Clocks, apb1: &mut APB1) -> Bus {
+        // fast mode; the F303 could do 1MHz, but only with the
+        // fast mode plus drive bits set in SYSCFG
+        I2c::new(i2c1, (scl, sda), 400.kHz().try_into().unwrap(), clocks, apb1)
+    }
+
+    // the board's pull-ups are weak, so the pins' own help
+    fn pins(mut gpiob: gpiob::Parts) -> (Scl, Sda) {
+        let (moder, otyper, afrl) = (&mut gpiob.moder, &mut gpiob.otyper, &mut gpiob.afrl);
+        let mut scl = gpiob.pb6.into_af_open_drain(moder, otyper, afrl);
+        let mut sda = gpiob.pb7.into_af_open_drain(moder, otyper, afrl);
+        scl.internal_pull_up(&mut gpiob.pupdr, true);
+        sda.internal_pull_up(&mut gpiob.pupdr, true);
+        (scl, sda)
+    }
+
+    /// Only once, the bus they share is a static. The manager's for
+    /// the accelerometer to get its own proxy from.
+    pub fn displays(
+        i2c1: pac::I2C1,
+        gpiob: gpiob::Parts,
+        clocks: Clocks,
+        apb1: &mut APB1,
+    ) -> (Displays, &'static Manager) {
+        let (scl, sda) = pins(gpiob);
+        let bus = bus(i2c1, scl, sda, clocks, apb1);
+        let manager: &'static Manager =
+            cortex_m::singleton!(: Manager = BusManagerSimple::new(bus)).unwrap();
+        // the panels share one proxy, the way they'd share the bus itself
+        let shared = cortex_m::singleton!(: Shared = RefCell::new(None)).unwrap();
+        (panel::lanes(manager.acquire_i2c(), shared), manager)
+    }
+
+    // PB6 or PB7 straight on GPIOB's registers, for clocking a stuck
+    // slave off the bus while the manager still has the pins
+    struct Line(u8);
+
+    impl OutputPin for Line {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            let gpiob = unsafe { &*pac::GPIOB::ptr() };
+            gpiob.bsrr.write(|w| unsafe { w.bits(1 << (16 + self.0)) });
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            let gpiob = unsafe { &*pac::GPIOB::ptr() };
+            gpiob.bsrr.write(|w| unsafe { w.bits(1 << self.0) });
+            Ok(())
+        }
+    }
+
+    impl InputPin for Line {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            let gpiob = unsafe { &*pac::GPIOB::ptr() };
+            Ok(gpiob.idr.read().bits() & 1 << self.0 != 0)
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    // MODER's two bits for PB6 and PB7
+    const MODE_MASK: u32 = 0b1111 << 12;
+    const MODE_OUTPUT: u32 = 0b0101 << 12;
+    const MODE_AF: u32 = 0b1010 << 12;
+
+    // The manager has the bus for good, so switch its pins over to plain
+    // open drain outputs, clock a stuck slave off SDA, switch them back and
+    // start I2C1 over. The rest of GPIOB is left alone, and the pins keep
+    // the open drain, pull-ups and AF4 that pins() gave them. The caller
+    // re-inits.
+    pub fn recover(delay: &mut Delay) {
+        microaleph::warn!("recovering I2C1");
+        let gpiob = unsafe { &*pac::GPIOB::ptr() };
+        let (mut scl, mut sda) = (Line(6), Line(7));
+        // high before they're outputs, so nothing glitches low
+        scl.set_high().ok();
+        sda.set_high().ok();
+        gpiob
+            .moder
+            .modify(|r, w| unsafe { w.bits(r.bits() & !MODE_MASK | MODE_OUTPUT) });
+        if !recover::clear_bus(&mut scl, &mut sda, delay) {
+            microaleph::error!("SDA still held low");
+        }
+        gpiob
+            .moder
+            .modify(|r, w| unsafe { w.bits(r.bits() & !MODE_MASK | MODE_AF) });
+        // its timing survives, only the state machine starts over, and PE
+        // has to stay low for three APB1 cycles
+        let i2c1 = unsafe { &*pac::I2C1::ptr() };
+        i2c1.cr1.modify(|_, w| w.pe().clear_bit());
+        cortex_m::asm::delay(16);
+        i2c1.cr1.modify(|_, w| w.pe().set_bit());
+    }
+
+    /// For the fault handlers, whoever had the pins is gone.
+    pub fn steal(clocks: Clocks) -> Displays {
+        let dp = unsafe { pac::Peripherals::steal() };
+        let mut rcc = dp.RCC.constrain();
+        let (scl, sda) = pins(dp.GPIOB.split(&mut rcc.ahb));
+        let bus = bus(dp.I2C1, scl, sda, clocks, &mut rcc.apb1);
+        // its own, the one main had still has the old bus
+        let manager: &'static Manager =
+            cortex_m::singleton!(: Manager = BusManagerSimple::new(bus)).unwrap();
+        let shared = cortex_m::singleton!(: Shared = RefCell::new(None)).unwrap();
+        panel::lanes(manager.acquire_i2c(), shared)
+    }
+}
+
+#[cfg(feature = "interface-spi")]
+mod spi {
+    use super::*;
+    use hal::spi::Spi;
+    use ssd1306::prelude::*;
+
+    pub type Sck = gpiob::PB13<gpio::AF5<gpio::PushPull>>;
+    pub type Miso = gpiob::PB14<gpio::AF5<gpio::PushPull>>;
+    pub type Mosi = gpiob::PB15<gpio::AF5<gpio::PushPull>>;
+    pub type Cs = gpiob::PB12<gpio::Output<gpio::PushPull>>;
+    pub type Dc = gpiob::PB11<gpio::Output<gpio::PushPull>>;
+    pub type Res = gpiob::PB10<gpio::Output<gpio::PushPull>>;
+    pub type Interface = SPIInterface<Spi<pac::SPI2, (Sck, Miso, Mosi)>, Dc, Cs>;
+
+    fn interface(
+        spi2: pac::SPI2,
+        mut gpiob: gpiob::Parts,
+        clocks: Clocks,
+        apb1: &mut APB1,
+    ) -> (Interface, Res) {
+        let (moder, otyper, afrh) = (&mut gpiob.moder, &mut gpiob.otyper, &mut gpiob.afrh);
+        let sck = gpiob.pb13.into_af_push_pull(moder, otyper, afrh);
+        let miso = gpiob.pb14.into_af_push_pull(moder, otyper, afrh);
+        let mosi = gpiob.pb15.into_af_push_pull(moder, otyper, afrh);
+        let cs = gpiob.pb12.into_push_pull_output(moder, otyper);
+        let dc = gpiob.pb11.into_push_pull_output(moder, otyper);
+        let res = gpiob.pb10.into_push_pull_output(moder, otyper);
+        // APB1 runs at 8MHz off the HSI, so 4MHz is as fast as SPI2 goes
+        let spi = Spi::new(spi2, (sck, miso, mosi), 4.MHz(), clocks, apb1);
+        (SPIInterface::new(spi, dc, cs), res)
+    }
+
+    pub fn displays(
+        spi2: pac::SPI2,
+        gpiob: gpiob::Parts,
+        clocks: Clocks,
+        apb1: &mut APB1,
+        delay: &mut Delay,
+    ) -> Displays {
+        let (interface, mut res) = interface(spi2, gpiob, clocks, apb1);
+        // SPI modules want a reset pulse before init, I2C ones do it on their own
+        res.set_high().ok();
+        delay.delay_ms(1u32);
+        res.set_low().ok();
+        delay.delay_ms(10u32);
+        res.set_high().ok();
+        panel::single(interface)
+    }
+
+    // Nothing holds an SPI bus, a re-init is all it takes
+    pub fn recover(_delay: &mut Delay) {}
+
+    /// For the fault handlers, whoever had the pins is gone.
+    pub fn steal(clocks: Clocks) -> Displays {
+        let dp = unsafe { pac::Peripherals::steal() };
+        let mut rcc = dp.RCC.constrain();
+        let gpiob = dp.GPIOB.split(&mut rcc.ahb);
+        let (interface, mut res) = interface(dp.SPI2, gpiob, clocks, &mut rcc.apb1);
+        // the panel may be halfway through something, start it over
+        res.set_low().ok();
+        cortex_m::asm::delay(50_000);
+        res.set_high().ok();
+        panel::single(interface)
+    }
+}
@@ -0,0 +1,192 @@
+//! What the slideshow needs from a board, and the boards that have it,
+//! picked with a `board-*` feature.
+//!
+//! Each board module owns its HAL, pins and peripherals and hands
+//! `main` a [`Bsp`]: the panels, the timers, an LED and a button, and
+//! whichever of the control ports, light sensor, accelerometer, gyro and
+//! settings storage it has. Everything about the slideshow itself lives
+//! in `main` and microaleph, once for every board.
+
+use display_interface::DisplayError;
+use microaleph::frame::Frame;
+use microaleph::gallery::Flash;
+#[cfg(not(feature = "board-blackpill-f411"))]
+use microaleph::gallery::Slot;
+use microaleph::motion::Event;
+#[cfg(feature = "window")]
+use microaleph::motion::Rates;
+use microaleph::protocol::{Error, Payload, Response};
+use microaleph::ring::Levels;
+use microaleph::rotation::Rotation;
+use microaleph::slideshow::Slideshow;
+
+use crate::images::Images;
+
+#[cfg(feature = "board-f3discovery")]
+mod f3discovery;
+#[cfg(feature = "board-blackpill-f401")]
+mod blackpill_f401;
+#[cfg(feature = "board-blackpill-f411")]
+mod blackpill_f411;
+
+#[cfg(feature = "board-f3discovery")]
+pub use f3discovery::F3Discovery as Bsp;
+#[cfg(feature = "board-blackpill-f401")]
+pub use blackpill_f401::BlackPillF401 as Bsp;
+#[cfg(feature = "board-blackpill-f411")]
+pub use blackpill_f411::BlackPillF411 as Bsp;
+
+const BOARDS: usize = cfg!(feature = "board-f3discovery") as usize
+    + cfg!(feature = "board-blackpill-f401") as usize
+    + cfg!(feature = "board-blackpill-f411") as usize;
+const _: () = assert!(BOARDS == 1, "pick one of the board-* features");
+
+#[cfg(all(feature = "gray4", not(feature = "board-blackpill-f411")))]
+compile_error!("gray4 needs gallery uploads, which only board-blackpill-f411 has room for");
+
+/// The board's timers, all free running.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Timer {
+    /// the slideshow's interval, see [`Board::interval`]
+    Slide,
+    /// once a second, for the burn-in guard
+    Second,
+    /// every 20ms, for the brightness fade and the button
+    Fade,
+    /// every 25ms, to flip bitplanes for gray galleries
+    #[cfg(feature = "gray4")]
+    Gray,
+}
+
+/// Where a request came from, so the reply goes back the same way.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Port {
+    #[cfg(feature = "board-blackpill-f411")]
+    Serial,
+    #[cfg(any(feature = "board-blackpill-f401", feature = "board-blackpill-f411"))]
+    Usb,
+}
+
+/// For boards with no room for gallery slots. There's never one of these,
+/// so uploads get turned down.
+#[cfg(not(feature = "board-blackpill-f411"))]
+pub enum NoFlash {}
+
+#[cfg(not(feature = "board-blackpill-f411"))]
+impl Flash for NoFlash {
+    fn capacity(&self) -> usize {
+        match *self {}
+    }
+
+    fn erase(&mut self, _slot: Slot) -> Result<(), Error> {
+        match *self {}
+    }
+
+    fn program(&mut self, _slot: Slot, _offset: usize, _data: &[u8]) -> Result<(), Error> {
+        match *self {}
+    }
+
+    fn read(&self, _slot: Slot) -> &[u8] {
+        match *self {}
+    }
+}
+
+pub trait Board: Sized {
+    /// Where uploaded galleries go, [`NoFlash`] without room for them.
+    type Flash: Flash;
+
+    /// The built in pictures.
+    const IMAGES: Images;
+    /// How the panel's mounted out of the box.
+    const ROTATION: Rotation;
+    /// How long each picture's up out of the box.
+    const INTERVAL_MS: u32;
+
+    /// Clocks, watchdog, panels, timers and ports. Only once. The gallery
+    /// flash comes separately so pictures can borrow from it while the
+    /// board draws.
+    fn take() -> (Self, Option<Self::Flash>);
+
+    /// For the fault handlers: take the panels over from scratch, whoever
+    /// had them is gone, and put `frame` on all of them.
+    fn show_fault(frame: &Frame);
+
+    /// How many panels, see `Slideshow::lane`.
+    fn lanes(&self) -> usize;
+
+    /// Bring `lane`'s panel up. Whatever it had is gone, so the next flush
+    /// sends all of it.
+    fn init(&mut self, lane: usize) -> Result<(), DisplayError>;
+
+    fn contrast(&mut self, lane: usize, contrast: u8) -> Result<(), DisplayError>;
+
+    /// The frame the next flush sends.
+    fn frame(&mut self) -> &mut Frame;
+
+    /// Whether a flush is still going out. Nothing else can use the
+    /// panels' bus until it's done.
+    fn busy(&self) -> bool {
+        false
+    }
+
+    /// Send what changed in the frame to `lane`. A flush that goes out in
+    /// the background calls `done` with whether it made it; one that's
+    /// out by the time this returns just returns how it went.
+    fn flush(&mut self, lane: usize, done: fn(bool)) -> Result<(), DisplayError>;
+
+    /// Free the panels' bus after it kept failing. The caller re-inits.
+    fn recover(&mut self);
+
+    /// Restart the slideshow timer with a new interval.
+    fn interval(&mut self, ms: u32);
+
+    /// Whether `timer` went off since it was last asked.
+    fn elapsed(&mut self, timer: Timer) -> bool;
+
+    fn delay_ms(&mut self, ms: u32);
+
+    fn led(&mut self, on: bool);
+
+    /// Light the ring of status LEDs, if there is one, see
+    /// microaleph::ring. Asked on every `Timer::Fade`.
+    fn ring(&mut self, _levels: &Levels) {}
+
+    /// Whether the user button is held down.
+    fn button(&mut self) -> bool;
+
+    /// How the board's being held changed, if it has an accelerometer.
+    /// Asked on every `Timer::Fade`.
+    fn motion(&mut self) -> Option<Event> {
+        None
+    }
+
+    /// How fast the board's turning, if it has a gyro, for panning the
+    /// `window` picture. Asked on every `Timer::Fade`.
+    #[cfg(feature = "window")]
+    fn gyro(&mut self) -> Option<Rates> {
+        None
+    }
+
+    /// A raw light sensor reading, if there's a sensor.
+    fn light(&mut self) -> Option<u16> {
+        None
+    }
+
+    /// The next whole frame from a control port, see microaleph::protocol.
+    fn request(&mut self) -> Option<(Port, Result<Payload, Error>)> {
+        None
+    }
+
+    fn reply(&mut self, _port: Port, _response: &Response) {}
+
+    /// Put back the settings `save` stored, if the board keeps them.
+    fn load(&self, _show: &mut Slideshow) {}
+
+    fn save(&mut self, _show: &Slideshow) {}
+
+    /// Tell the watchdog all's well.
+    fn feed(&mut self);
+
+    /// Sleep until something happens, while a flush goes out.
+    fn idle(&mut self) {}
+}
@@ -0,0 +1,53 @@
+//! Panic and HardFault handlers that put what went wrong on the screen.
+//!
+//! Whatever owned the display is gone by the time we get here, so the
+//! board steals the peripherals and brings up the panels' bus and the
+//! controllers from scratch, then the report goes up and we halt.
+
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use cortex_m::{asm, interrupt};
+use cortex_m_rt::{exception, ExceptionFrame};
+use microaleph::fault::{self, FaultRegs, Lines};
+
+use crate::board::{Board, Bsp};
+use crate::panel::PANEL;
+
+// set on the first fault so a fault while reporting doesn't recurse
+static FAULTED: AtomicBool = AtomicBool::new(false);
+
+fn show_and_halt(lines: &Lines) -> ! {
+    if !FAULTED.swap(true, Ordering::SeqCst) {
+        let mut frame = PANEL.frame();
+        fault::show(&mut frame, lines);
+        Bsp::show_fault(&frame);
+    }
+    loop {
+        asm::wfi();
+    }
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    interrupt::disable();
+    microaleph::error!("{}", defmt::Display2Format(info));
+    show_and_halt(&fault::panic_lines(info))
+}
+
+#[exception]
+unsafe fn HardFault(ef: &ExceptionFrame) -> ! {
+    let scb = &*cortex_m::peripheral::SCB::PTR;
+    let regs = FaultRegs {
+        pc: ef.pc(),
+        lr: ef.lr(),
+        cfsr: scb.cfsr.read(),
+    };
+    microaleph::error!(
+        "hardfault pc={=u32:#x} lr={=u32:#x} cfsr={=u32:#x}",
+        regs.pc,
+        regs.lr,
+        regs.cfsr
+    );
+    show_and_halt(&fault::hardfault_lines(&regs))
+}
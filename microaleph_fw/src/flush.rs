@@ -0,0 +1,53 @@
+//! Flushing a [`Frame`] straight away, for boards and buses without DMA.
+//!
+//! Only what changed since the lane's last flush goes out. On SPI even a
+//! whole screen is about a millisecond; on I2C it's 25ms the loop waits
+//! for, which is fine for a slideshow.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use microaleph::frame::Frame;
+use microaleph::oled::Oled;
+use microaleph::scan::LANES;
+
+use crate::panel::PANEL;
+
+/// The frame to draw into, and what each lane's panel has from the last one.
+pub struct Blocking {
+    frame: Frame,
+    shown: [Frame; LANES],
+    known: [bool; LANES],
+}
+
+impl Blocking {
+    pub fn new() -> Self {
+        Blocking {
+            frame: PANEL.frame(),
+            shown: [(); LANES].map(|_| PANEL.frame()),
+            known: [false; LANES],
+        }
+    }
+
+    pub fn frame(&mut self) -> &mut Frame {
+        &mut self.frame
+    }
+
+    /// Send everything to `lane` next time, say after an init.
+    pub fn forget(&mut self, lane: usize) {
+        self.known[lane] = false;
+    }
+
+    /// Send what changed to `lane`'s `display`.
+    pub fn flush<DI: WriteOnlyDataCommand>(
+        &mut self,
+        lane: usize,
+        display: &mut Oled<DI>,
+    ) -> Result<(), DisplayError> {
+        let shown = if self.known[lane] { Some(&self.shown[lane]) } else { None };
+        let result = display.flush(&self.frame, shown);
+        self.known[lane] = result.is_ok();
+        if result.is_ok() {
+            self.shown[lane].clone_from(&self.frame);
+        }
+        result
+    }
+}
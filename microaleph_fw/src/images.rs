@@ -0,0 +1,415 @@
+//! The pictures built into the firmware, for when there's no uploaded
+//! gallery. Boards pick the set that suits how their panel's mounted.
+
+use microaleph::picture::Picture;
+
+pub struct Images {
+    pub pictures: &'static [&'static [u8; 1024]],
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Images {
+    pub fn len(&self) -> usize {
+        self.pictures.len()
+    }
+
+    pub fn picture(&self, i: usize) -> Picture<'static> {
+        Picture::new(&self.pictures[i][..], self.width, self.height)
+    }
+}
+
+/// 512x256, for the `window` feature to look around, made with
+/// images/mosaic.sh.
+#[cfg(feature = "window")]
+pub const SHEET: Picture<'static> = Picture {
+    data: include_bytes!("../../images/512x256/001.gray"),
+    width: 512,
+    height: 256,
+};
+
+/// 128x64, for panels the right way up.
+#[cfg(any(feature = "board-f3discovery", feature = "board-blackpill-f411"))]
+pub const LANDSCAPE: Images = Images {
+    pictures: &[
+        include_bytes!("../../images/128x64/001.gray"),
+        include_bytes!("../../images/128x64/002.gray"),
+        include_bytes!("../../images/128x64/003.gray"),
+        include_bytes!("../../images/128x64/004.gray"),
+        include_bytes!("../../images/128x64/005.gray"),
+        include_bytes!("../../images/128x64/006.gray"),
+        include_bytes!("../../images/128x64/007.gray"),
+        include_bytes!("../../images/128x64/008.gray"),
+        include_bytes!("../../images/128x64/009.gray"),
+        include_bytes!("../../images/128x64/010.gray"),
+        include_bytes!("../../images/128x64/011.gray"),
+        include_bytes!("../../images/128x64/012.gray"),
+        include_bytes!("../../images/128x64/013.gray"),
+        include_bytes!("../../images/128x64/014.gray"),
+        include_bytes!("../../images/128x64/015.gray"),
+        include_bytes!("../../images/128x64/016.gray"),
+        include_bytes!("../../images/128x64/017.gray"),
+        include_bytes!("../../images/128x64/018.gray"),
+        include_bytes!("../../images/128x64/019.gray"),
+        include_bytes!("../../images/128x64/020.gray"),
+        include_bytes!("../../images/128x64/021.gray"),
+        include_bytes!("../../images/128x64/022.gray"),
+        include_bytes!("../../images/128x64/023.gray"),
+        include_bytes!("../../images/128x64/024.gray"),
+        include_bytes!("../../images/128x64/025.gray"),
+        include_bytes!("../../images/128x64/026.gray"),
+        include_bytes!("../../images/128x64/027.gray"),
+        include_bytes!("../../images/128x64/028.gray"),
+        include_bytes!("../../images/128x64/029.gray"),
+        include_bytes!("../../images/128x64/030.gray"),
+        include_bytes!("../../images/128x64/031.gray"),
+        include_bytes!("../../images/128x64/032.gray"),
+        include_bytes!("../../images/128x64/033.gray"),
+        include_bytes!("../../images/128x64/034.gray"),
+        include_bytes!("../../images/128x64/035.gray"),
+        include_bytes!("../../images/128x64/036.gray"),
+        include_bytes!("../../images/128x64/037.gray"),
+        include_bytes!("../../images/128x64/038.gray"),
+        include_bytes!("../../images/128x64/039.gray"),
+        include_bytes!("../../images/128x64/040.gray"),
+        include_bytes!("../../images/128x64/041.gray"),
+        include_bytes!("../../images/128x64/042.gray"),
+        include_bytes!("../../images/128x64/043.gray"),
+        include_bytes!("../../images/128x64/044.gray"),
+        include_bytes!("../../images/128x64/045.gray"),
+        include_bytes!("../../images/128x64/046.gray"),
+        include_bytes!("../../images/128x64/047.gray"),
+        include_bytes!("../../images/128x64/048.gray"),
+        include_bytes!("../../images/128x64/049.gray"),
+        include_bytes!("../../images/128x64/050.gray"),
+        include_bytes!("../../images/128x64/051.gray"),
+        include_bytes!("../../images/128x64/052.gray"),
+        include_bytes!("../../images/128x64/053.gray"),
+        include_bytes!("../../images/128x64/054.gray"),
+        include_bytes!("../../images/128x64/055.gray"),
+        include_bytes!("../../images/128x64/056.gray"),
+        include_bytes!("../../images/128x64/057.gray"),
+        include_bytes!("../../images/128x64/058.gray"),
+        include_bytes!("../../images/128x64/059.gray"),
+        include_bytes!("../../images/128x64/060.gray"),
+        include_bytes!("../../images/128x64/061.gray"),
+        include_bytes!("../../images/128x64/062.gray"),
+        include_bytes!("../../images/128x64/063.gray"),
+        include_bytes!("../../images/128x64/064.gray"),
+        include_bytes!("../../images/128x64/065.gray"),
+        include_bytes!("../../images/128x64/066.gray"),
+        include_bytes!("../../images/128x64/067.gray"),
+        include_bytes!("../../images/128x64/068.gray"),
+        include_bytes!("../../images/128x64/069.gray"),
+        include_bytes!("../../images/128x64/070.gray"),
+        include_bytes!("../../images/128x64/071.gray"),
+        include_bytes!("../../images/128x64/072.gray"),
+        include_bytes!("../../images/128x64/073.gray"),
+        include_bytes!("../../images/128x64/074.gray"),
+        include_bytes!("../../images/128x64/075.gray"),
+        include_bytes!("../../images/128x64/076.gray"),
+        include_bytes!("../../images/128x64/077.gray"),
+        include_bytes!("../../images/128x64/078.gray"),
+        include_bytes!("../../images/128x64/079.gray"),
+        include_bytes!("../../images/128x64/080.gray"),
+        include_bytes!("../../images/128x64/081.gray"),
+        include_bytes!("../../images/128x64/082.gray"),
+        include_bytes!("../../images/128x64/083.gray"),
+        include_bytes!("../../images/128x64/084.gray"),
+        include_bytes!("../../images/128x64/085.gray"),
+        include_bytes!("../../images/128x64/086.gray"),
+        include_bytes!("../../images/128x64/087.gray"),
+        include_bytes!("../../images/128x64/088.gray"),
+        include_bytes!("../../images/128x64/089.gray"),
+        include_bytes!("../../images/128x64/090.gray"),
+        include_bytes!("../../images/128x64/091.gray"),
+        include_bytes!("../../images/128x64/092.gray"),
+        include_bytes!("../../images/128x64/093.gray"),
+        include_bytes!("../../images/128x64/094.gray"),
+        include_bytes!("../../images/128x64/095.gray"),
+        include_bytes!("../../images/128x64/096.gray"),
+        include_bytes!("../../images/128x64/097.gray"),
+        include_bytes!("../../images/128x64/098.gray"),
+        include_bytes!("../../images/128x64/099.gray"),
+        include_bytes!("../../images/128x64/100.gray"),
+        include_bytes!("../../images/128x64/101.gray"),
+        include_bytes!("../../images/128x64/102.gray"),
+        include_bytes!("../../images/128x64/103.gray"),
+        include_bytes!("../../images/128x64/104.gray"),
+        include_bytes!("../../images/128x64/105.gray"),
+        include_bytes!("../../images/128x64/106.gray"),
+        include_bytes!("../../images/128x64/107.gray"),
+        include_bytes!("../../images/128x64/108.gray"),
+        include_bytes!("../../images/128x64/109.gray"),
+        include_bytes!("../../images/128x64/100.gray"),
+        include_bytes!("../../images/128x64/111.gray"),
+        include_bytes!("../../images/128x64/112.gray"),
+        include_bytes!("../../images/128x64/113.gray"),
+        include_bytes!("../../images/128x64/114.gray"),
+        include_bytes!("../../images/128x64/115.gray"),
+        include_bytes!("../../images/128x64/116.gray"),
+        include_bytes!("../../images/128x64/117.gray"),
+        include_bytes!("../../images/128x64/118.gray"),
+        include_bytes!("../../images/128x64/119.gray"),
+        include_bytes!("../../images/128x64/120.gray"),
+        include_bytes!("../../images/128x64/121.gray"),
+        include_bytes!("../../images/128x64/122.gray"),
+        include_bytes!("../../images/128x64/123.gray"),
+        include_bytes!("../../images/128x64/124.gray"),
+        include_bytes!("../../images/128x64/125.gray"),
+        include_bytes!("../../images/128x64/126.gray"),
+        include_bytes!("../../images/128x64/127.gray"),
+        include_bytes!("../../images/128x64/128.gray"),
+        include_bytes!("../../images/128x64/129.gray"),
+        include_bytes!("../../images/128x64/130.gray"),
+        include_bytes!("../../images/128x64/131.gray"),
+        include_bytes!("../../images/128x64/132.gray"),
+        include_bytes!("../../images/128x64/133.gray"),
+        include_bytes!("../../images/128x64/134.gray"),
+        include_bytes!("../../images/128x64/135.gray"),
+        include_bytes!("../../images/128x64/136.gray"),
+        include_bytes!("../../images/128x64/137.gray"),
+        include_bytes!("../../images/128x64/138.gray"),
+        include_bytes!("../../images/128x64/139.gray"),
+        include_bytes!("../../images/128x64/140.gray"),
+        include_bytes!("../../images/128x64/141.gray"),
+        include_bytes!("../../images/128x64/142.gray"),
+        include_bytes!("../../images/128x64/143.gray"),
+        include_bytes!("../../images/128x64/144.gray"),
+        include_bytes!("../../images/128x64/145.gray"),
+        include_bytes!("../../images/128x64/146.gray"),
+        include_bytes!("../../images/128x64/147.gray"),
+        include_bytes!("../../images/128x64/148.gray"),
+        include_bytes!("../../images/128x64/149.gray"),
+        include_bytes!("../../images/128x64/150.gray"),
+        include_bytes!("../../images/128x64/151.gray"),
+        include_bytes!("../../images/128x64/152.gray"),
+        include_bytes!("../../images/128x64/153.gray"),
+        include_bytes!("../../images/128x64/154.gray"),
+        include_bytes!("../../images/128x64/155.gray"),
+        include_bytes!("../../images/128x64/156.gray"),
+        include_bytes!("../../images/128x64/157.gray"),
+        include_bytes!("../../images/128x64/158.gray"),
+        include_bytes!("../../images/128x64/159.gray"),
+        include_bytes!("../../images/128x64/150.gray"),
+        include_bytes!("../../images/128x64/161.gray"),
+        include_bytes!("../../images/128x64/162.gray"),
+        include_bytes!("../../images/128x64/163.gray"),
+        include_bytes!("../../images/128x64/164.gray"),
+        include_bytes!("../../images/128x64/165.gray"),
+        include_bytes!("../../images/128x64/166.gray"),
+        include_bytes!("../../images/128x64/167.gray"),
+        include_bytes!("../../images/128x64/168.gray"),
+        include_bytes!("../../images/128x64/169.gray"),
+        include_bytes!("../../images/128x64/170.gray"),
+        include_bytes!("../../images/128x64/171.gray"),
+        include_bytes!("../../images/128x64/172.gray"),
+        include_bytes!("../../images/128x64/173.gray"),
+        include_bytes!("../../images/128x64/174.gray"),
+        include_bytes!("../../images/128x64/175.gray"),
+        include_bytes!("../../images/128x64/176.gray"),
+        include_bytes!("../../images/128x64/177.gray"),
+        include_bytes!("../../images/128x64/178.gray"),
+        include_bytes!("../../images/128x64/179.gray"),
+        include_bytes!("../../images/128x64/180.gray"),
+        include_bytes!("../../images/128x64/181.gray"),
+        include_bytes!("../../images/128x64/182.gray"),
+        include_bytes!("../../images/128x64/183.gray"),
+        include_bytes!("../../images/128x64/184.gray"),
+        include_bytes!("../../images/128x64/185.gray"),
+        include_bytes!("../../images/128x64/186.gray"),
+        include_bytes!("../../images/128x64/187.gray"),
+        include_bytes!("../../images/128x64/188.gray"),
+        include_bytes!("../../images/128x64/189.gray"),
+        include_bytes!("../../images/128x64/190.gray"),
+        include_bytes!("../../images/128x64/191.gray"),
+        include_bytes!("../../images/128x64/192.gray"),
+        include_bytes!("../../images/128x64/193.gray"),
+        include_bytes!("../../images/128x64/194.gray"),
+        include_bytes!("../../images/128x64/195.gray"),
+    ],
+    width: 128,
+    height: 64,
+};
+
+/// 64x128, for panels on their side.
+#[cfg(feature = "board-blackpill-f401")]
+pub const PORTRAIT: Images = Images {
+    pictures: &[
+        include_bytes!("../../images/64x128/001.gray"),
+        include_bytes!("../../images/64x128/002.gray"),
+        include_bytes!("../../images/64x128/003.gray"),
+        include_bytes!("../../images/64x128/004.gray"),
+        include_bytes!("../../images/64x128/005.gray"),
+        include_bytes!("../../images/64x128/006.gray"),
+        include_bytes!("../../images/64x128/007.gray"),
+        include_bytes!("../../images/64x128/008.gray"),
+        include_bytes!("../../images/64x128/009.gray"),
+        include_bytes!("../../images/64x128/010.gray"),
+        include_bytes!("../../images/64x128/011.gray"),
+        include_bytes!("../../images/64x128/012.gray"),
+        include_bytes!("../../images/64x128/013.gray"),
+        include_bytes!("../../images/64x128/014.gray"),
+        include_bytes!("../../images/64x128/015.gray"),
+        include_bytes!("../../images/64x128/016.gray"),
+        include_bytes!("../../images/64x128/017.gray"),
+        include_bytes!("../../images/64x128/018.gray"),
+        include_bytes!("../../images/64x128/019.gray"),
+        include_bytes!("../../images/64x128/020.gray"),
+        include_bytes!("../../images/64x128/021.gray"),
+        include_bytes!("../../images/64x128/022.gray"),
+        include_bytes!("../../images/64x128/023.gray"),
+        include_bytes!("../../images/64x128/024.gray"),
+        include_bytes!("../../images/64x128/025.gray"),
+        include_bytes!("../../images/64x128/026.gray"),
+        include_bytes!("../../images/64x128/027.gray"),
+        include_bytes!("../../images/64x128/028.gray"),
+        include_bytes!("../../images/64x128/029.gray"),
+        include_bytes!("../../images/64x128/030.gray"),
+        include_bytes!("../../images/64x128/031.gray"),
+        include_bytes!("../../images/64x128/032.gray"),
+        include_bytes!("../../images/64x128/033.gray"),
+        include_bytes!("../../images/64x128/034.gray"),
+        include_bytes!("../../images/64x128/035.gray"),
+        include_bytes!("../../images/64x128/036.gray"),
+        include_bytes!("../../images/64x128/037.gray"),
+        include_bytes!("../../images/64x128/038.gray"),
+        include_bytes!("../../images/64x128/039.gray"),
+        include_bytes!("../../images/64x128/040.gray"),
+        include_bytes!("../../images/64x128/041.gray"),
+        include_bytes!("../../images/64x128/042.gray"),
+        include_bytes!("../../images/64x128/043.gray"),
+        include_bytes!("../../images/64x128/044.gray"),
+        include_bytes!("../../images/64x128/045.gray"),
+        include_bytes!("../../images/64x128/046.gray"),
+        include_bytes!("../../images/64x128/047.gray"),
+        include_bytes!("../../images/64x128/048.gray"),
+        include_bytes!("../../images/64x128/049.gray"),
+        include_bytes!("../../images/64x128/050.gray"),
+        include_bytes!("../../images/64x128/051.gray"),
+        include_bytes!("../../images/64x128/052.gray"),
+        include_bytes!("../../images/64x128/053.gray"),
+        include_bytes!("../../images/64x128/054.gray"),
+        include_bytes!("../../images/64x128/055.gray"),
+        include_bytes!("../../images/64x128/056.gray"),
+        include_bytes!("../../images/64x128/057.gray"),
+        include_bytes!("../../images/64x128/058.gray"),
+        include_bytes!("../../images/64x128/059.gray"),
+        include_bytes!("../../images/64x128/060.gray"),
+        include_bytes!("../../images/64x128/061.gray"),
+        include_bytes!("../../images/64x128/062.gray"),
+        include_bytes!("../../images/64x128/063.gray"),
+        include_bytes!("../../images/64x128/064.gray"),
+        include_bytes!("../../images/64x128/065.gray"),
+        include_bytes!("../../images/64x128/066.gray"),
+        include_bytes!("../../images/64x128/067.gray"),
+        include_bytes!("../../images/64x128/068.gray"),
+        include_bytes!("../../images/64x128/069.gray"),
+        include_bytes!("../../images/64x128/070.gray"),
+        include_bytes!("../../images/64x128/071.gray"),
+        include_bytes!("../../images/64x128/072.gray"),
+        include_bytes!("../../images/64x128/073.gray"),
+        include_bytes!("../../images/64x128/074.gray"),
+        include_bytes!("../../images/64x128/075.gray"),
+        include_bytes!("../../images/64x128/076.gray"),
+        include_bytes!("../../images/64x128/077.gray"),
+        include_bytes!("../../images/64x128/078.gray"),
+        include_bytes!("../../images/64x128/079.gray"),
+        include_bytes!("../../images/64x128/080.gray"),
+        include_bytes!("../../images/64x128/081.gray"),
+        include_bytes!("../../images/64x128/082.gray"),
+        include_bytes!("../../images/64x128/083.gray"),
+        include_bytes!("../../images/64x128/084.gray"),
+        include_bytes!("../../images/64x128/085.gray"),
+        include_bytes!("../../images/64x128/086.gray"),
+        include_bytes!("../../images/64x128/087.gray"),
+        include_bytes!("../../images/64x128/088.gray"),
+        include_bytes!("../../images/64x128/089.gray"),
+        include_bytes!("../../images/64x128/090.gray"),
+        include_bytes!("../../images/64x128/091.gray"),
+        include_bytes!("../../images/64x128/092.gray"),
+        include_bytes!("../../images/64x128/093.gray"),
+        include_bytes!("../../images/64x128/094.gray"),
+        include_bytes!("../../images/64x128/095.gray"),
+        include_bytes!("../../images/64x128/096.gray"),
+        include_bytes!("../../images/64x128/097.gray"),
+        include_bytes!("../../images/64x128/098.gray"),
+        include_bytes!("../../images/64x128/099.gray"),
+        include_bytes!("../../images/64x128/100.gray"),
+        include_bytes!("../../images/64x128/101.gray"),
+        include_bytes!("../../images/64x128/102.gray"),
+        include_bytes!("../../images/64x128/103.gray"),
+        include_bytes!("../../images/64x128/104.gray"),
+        include_bytes!("../../images/64x128/105.gray"),
+        include_bytes!("../../images/64x128/106.gray"),
+        include_bytes!("../../images/64x128/107.gray"),
+        include_bytes!("../../images/64x128/108.gray"),
+        include_bytes!("../../images/64x128/109.gray"),
+        include_bytes!("../../images/64x128/100.gray"),
+        include_bytes!("../../images/64x128/111.gray"),
+        include_bytes!("../../images/64x128/112.gray"),
+        include_bytes!("../../images/64x128/113.gray"),
+        include_bytes!("../../images/64x128/114.gray"),
+        include_bytes!("../../images/64x128/115.gray"),
+        include_bytes!("../../images/64x128/116.gray"),
+        include_bytes!("../../images/64x128/117.gray"),
+        include_bytes!("../../images/64x128/118.gray"),
+        include_bytes!("../../images/64x128/119.gray"),
+        include_bytes!("../../images/64x128/120.gray"),
+        include_bytes!("../../images/64x128/121.gray"),
+        include_bytes!("../../images/64x128/122.gray"),
+        include_bytes!("../../images/64x128/123.gray"),
+        include_bytes!("../../images/64x128/124.gray"),
+        include_bytes!("../../images/64x128/125.gray"),
+        include_bytes!("../../images/64x128/126.gray"),
+        include_bytes!("../../images/64x128/127.gray"),
+        include_bytes!("../../images/64x128/128.gray"),
+        include_bytes!("../../images/64x128/129.gray"),
+        include_bytes!("../../images/64x128/130.gray"),
+        include_bytes!("../../images/64x128/131.gray"),
+        include_bytes!("../../images/64x128/132.gray"),
+        include_bytes!("../../images/64x128/133.gray"),
+        include_bytes!("../../images/64x128/134.gray"),
+        include_bytes!("../../images/64x128/135.gray"),
+        include_bytes!("../../images/64x128/136.gray"),
+        include_bytes!("../../images/64x128/137.gray"),
+        include_bytes!("../../images/64x128/138.gray"),
+        include_bytes!("../../images/64x128/139.gray"),
+        include_bytes!("../../images/64x128/140.gray"),
+        include_bytes!("../../images/64x128/141.gray"),
+        include_bytes!("../../images/64x128/142.gray"),
+        include_bytes!("../../images/64x128/143.gray"),
+        include_bytes!("../../images/64x128/144.gray"),
+        include_bytes!("../../images/64x128/145.gray"),
+        include_bytes!("../../images/64x128/146.gray"),
+        include_bytes!("../../images/64x128/147.gray"),
+        include_bytes!("../../images/64x128/148.gray"),
+        include_bytes!("../../images/64x128/149.gray"),
+        include_bytes!("../../images/64x128/150.gray"),
+        include_bytes!("../../images/64x128/151.gray"),
+        include_bytes!("../../images/64x128/152.gray"),
+        include_bytes!("../../images/64x128/153.gray"),
+        include_bytes!("../../images/64x128/154.gray"),
+        include_bytes!("../../images/64x128/155.gray"),
+        include_bytes!("../../images/64x128/156.gray"),
+        include_bytes!("../../images/64x128/157.gray"),
+        include_bytes!("../../images/64x128/158.gray"),
+        include_bytes!("../../images/64x128/159.gray"),
+        include_bytes!("../../images/64x128/150.gray"),
+        include_bytes!("../../images/64x128/161.gray"),
+        include_bytes!("../../images/64x128/162.gray"),
+        include_bytes!("../../images/64x128/163.gray"),
+        include_bytes!("../../images/64x128/164.gray"),
+        include_bytes!("../../images/64x128/165.gray"),
+        include_bytes!("../../images/64x128/166.gray"),
+        include_bytes!("../../images/64x128/167.gray"),
+        include_bytes!("../../images/64x128/168.gray"),
+        include_bytes!("../../images/64x128/169.gray"),
+        include_bytes!("../../images/64x128/170.gray"),
+        include_bytes!("../../images/64x128/171.gray"),
+        include_bytes!("../../images/64x128/172.gray"),
+        include_bytes!("../../images/64x128/173.gray"),
+    ],
+    width: 64,
+    height: 128,
+};
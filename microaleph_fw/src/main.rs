@@ -0,0 +1,374 @@
+#![no_main]
+#![no_std]
+
+#[cfg(feature = "defmt")]
+use defmt_rtt as _;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use cortex_m_rt::entry;
+use display_interface::DisplayError;
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use microaleph::brightness::{Ambient, Fader};
+use microaleph::burnin::{self, Guard, Inverted};
+use microaleph::frame::Frame;
+use microaleph::gallery::{Flash, Gallery, Uploader};
+#[cfg(feature = "gray4")]
+use microaleph::gray;
+use microaleph::motion::Event;
+use microaleph::picture::Picture;
+use microaleph::protocol::{Command, Error, Mode, Request, Response};
+use microaleph::recover::{Action, Health, Policy};
+use microaleph::ring::{self, Ring};
+use microaleph::rotation::Rotated;
+use microaleph::scan::LANES;
+use microaleph::slideshow::Slideshow;
+use microaleph::window::Window;
+
+mod board;
+mod fault;
+// the F411 flushes its I2C panels by DMA
+#[cfg(not(all(feature = "board-blackpill-f411", feature = "interface-i2c")))]
+mod flush;
+mod images;
+mod panel;
+
+use board::{Board, Bsp, Timer};
+use panel::PANEL;
+
+// set from the DMA interrupt when a flush didn't make it
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+// https://stackoverflow.com/questions/58075821/rust-embedded-binary-size
+#[inline]
+fn my_draw(
+    show: &Slideshow,
+    guard: &Guard,
+    picture: Picture,
+    pan: Option<&Window>,
+    frame: &mut Frame,
+) {
+    frame.clear(BinaryColor::Off).ok();
+    let mut turned = Rotated::new(frame, show.rotation);
+    if guard.exercising() {
+        guard.draw_exercise(&mut turned).ok();
+    } else {
+        let mut moved = turned.translated(guard.offset());
+        if guard.inverted() {
+            let mut inverted = Inverted(&mut moved);
+            inverted.clear(BinaryColor::Off).ok();
+            my_picture(show, picture, pan, &mut inverted).ok();
+        } else {
+            my_picture(show, picture, pan, &mut moved).ok();
+        }
+    }
+}
+
+// Draw and start the flush to `lane`, `flushed` hears if it goes out in
+// the background
+fn my_flush(
+    board: &mut Bsp,
+    lane: usize,
+    contrast: u8,
+    draw: impl FnOnce(&mut Frame),
+) -> Result<(), DisplayError> {
+    draw(board.frame());
+    // init puts the contrast back to the default
+    board.contrast(lane, contrast)?;
+    board.flush(lane, flushed)
+}
+
+fn flushed(ok: bool) {
+    if !ok {
+        FAILED.store(true, Ordering::SeqCst);
+    }
+}
+
+fn my_picture<D: DrawTarget<Color = BinaryColor>>(
+    show: &Slideshow,
+    picture: Picture,
+    pan: Option<&Window>,
+    display: &mut D,
+) -> Result<(), D::Error> {
+    match (show.mode, pan) {
+        // the window on the big picture, in place of the slideshow's
+        #[cfg(feature = "window")]
+        (Mode::Image, Some(window)) => window.draw(&images::SHEET, display)?,
+        // galleries made for another panel get scaled to fit
+        (Mode::Image, _) => picture.draw_fitted(display)?,
+        (Mode::Text, _) => show.draw_status(display)?,
+    }
+    Ok(())
+}
+
+// Uploaded galleries win over the built in images, the one facing the
+// way the panel is turned if there's a choice
+fn my_gallery<F: Flash>(flash: &Option<F>, show: &mut Slideshow) -> Option<Gallery> {
+    let panel = Size::new(PANEL.width as u32, PANEL.height as u32);
+    let size = show.rotation.size(panel);
+    let (width, height) = (size.width as usize, size.height as usize);
+    let gallery = flash
+        .as_ref()
+        .and_then(|f| Gallery::select_facing(f, width, height));
+    show.len = gallery.map_or(Bsp::IMAGES.len(), |g| g.frames);
+    show.index %= show.len;
+    gallery
+}
+
+// Bitplane `p` of picture `i`, from the gallery if there is one
+fn my_plane<F: Flash>(
+    flash: &Option<F>,
+    gallery: Option<Gallery>,
+    i: usize,
+    p: usize,
+) -> Picture<'_> {
+    match (gallery, flash) {
+        (Some(g), Some(f)) => Picture::new(g.plane(f, i, p), g.width, g.height),
+        _ => Bsp::IMAGES.picture(i),
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    microaleph::info!("boot");
+    let (mut board, mut flash) = Bsp::take();
+    let lanes = board.lanes();
+    let mut uploader = Uploader::new();
+
+    // Anti burn-in, the knobs are in microaleph::burnin::Config
+    let mut guard = Guard::new(burnin::Config::default());
+    let mut ambient = Ambient::new();
+    let mut leds = Ring::new();
+    #[cfg(feature = "gray4")]
+    let mut dither = gray::Dither::new();
+    #[cfg(feature = "window")]
+    let mut window = {
+        let sheet = Size::new(images::SHEET.width as u32, images::SHEET.height as u32);
+        Window::new(microaleph::window::Config::default(), sheet)
+    };
+
+    // one bus, so one health between the lanes
+    let mut health = Health::new(Policy::default());
+    let mut needs_init = [true; LANES];
+    let mut needs_draw = [true; LANES];
+    // where the last flush went, a failure is about that lane
+    let mut sent = 0;
+    let mut held = false;
+    // paused by lying face down, rather than by a command
+    let mut laid_down = false;
+    let mut led = false;
+
+    let mut show = Slideshow::new(Bsp::IMAGES.len(), 0, Bsp::INTERVAL_MS);
+    show.rotation = Bsp::ROTATION;
+    board.load(&mut show);
+    let mut gallery = my_gallery(&flash, &mut show);
+    let mut fader = Fader::new(show.brightness);
+    loop {
+        if board.elapsed(Timer::Slide) && show.tick() {
+            guard.activity();
+            needs_draw = [true; LANES];
+            led = !led;
+            board.led(led);
+        }
+
+        if board.elapsed(Timer::Second) && guard.second() {
+            needs_draw = [true; LANES];
+        }
+
+        if board.elapsed(Timer::Fade) {
+            // 20ms apart is debounce enough, a press moves on a picture
+            let pressed = board.button();
+            if pressed && !held {
+                show.next();
+                guard.activity();
+                needs_draw = [true; LANES];
+            }
+            held = pressed;
+
+            match board.motion() {
+                Some(Event::Shaken) => {
+                    show.next();
+                    guard.activity();
+                    needs_draw = [true; LANES];
+                }
+                Some(Event::Turned(rotation)) => {
+                    show.rotation = Bsp::ROTATION.plus(rotation);
+                    gallery = my_gallery(&flash, &mut show);
+                    guard.activity();
+                    needs_draw = [true; LANES];
+                }
+                Some(Event::FaceDown) if !show.paused => {
+                    show.paused = true;
+                    laid_down = true;
+                }
+                Some(Event::FaceUp) if laid_down => {
+                    show.paused = false;
+                    laid_down = false;
+                }
+                _ => {}
+            }
+
+            // a pause holds the window still too
+            #[cfg(feature = "window")]
+            if !show.paused {
+                let panel = Size::new(PANEL.width as u32, PANEL.height as u32);
+                if window.update(board.gyro(), 20, show.rotation.size(panel)) {
+                    needs_draw = [true; LANES];
+                }
+            }
+
+            let lit = if health.is_failing() {
+                ring::Show::Blink(ring::code::BUS)
+            } else if show.paused {
+                ring::Show::Breathing
+            } else {
+                ring::Show::Position { index: show.index, len: show.len }
+            };
+            board.ring(&leds.tick(20, lit));
+
+            // the contrast waits for the bus like everything else
+            if !board.busy() {
+                let reading = board.light().map(|raw| ambient.sense(raw));
+                let level = match reading {
+                    Some(reading) if show.auto => show.curve.contrast(reading),
+                    _ => show.brightness,
+                };
+                // contrast is a couple of bytes, no need for a whole redraw
+                if let Some(contrast) = fader.tick(guard.brightness(level)) {
+                    for lane in 0..lanes {
+                        if !needs_init[lane] && board.contrast(lane, contrast).is_err() {
+                            needs_draw[lane] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        while let Some((port, payload)) = board.request() {
+            let response = match payload.as_deref().map_err(|e| *e).and_then(Request::parse) {
+                Ok(Request::Control(command)) => {
+                    let response = show.apply(command);
+                    if response == Response::Ok {
+                        microaleph::info!("command {}", defmt::Debug2Format(&command));
+                        match command {
+                            Command::Interval(ms) => board.interval(ms),
+                            Command::Brightness(_) | Command::Auto(_) | Command::Curve(_) => {
+                                board.save(&show)
+                            }
+                            Command::Rotation(_) => {
+                                board.save(&show);
+                                gallery = my_gallery(&flash, &mut show);
+                            }
+                            _ => {}
+                        }
+                        // text mode shows the settings, so redraw for any change
+                        guard.activity();
+                        needs_draw = [true; LANES];
+                    }
+                    response
+                }
+                Ok(Request::Upload(upload)) => match flash.as_mut() {
+                    Some(flash) => {
+                        // erasing a sector takes up to 4 seconds, which the
+                        // board's watchdog leaves room for from here
+                        board.feed();
+                        match uploader.handle(flash, gallery, upload) {
+                            Ok(Some(uploaded)) => {
+                                microaleph::info!("uploaded {=usize} frames", uploaded.frames);
+                                gallery = Some(uploaded);
+                                show.len = uploaded.frames;
+                                show.index = 0;
+                                guard.activity();
+                                needs_draw = [true; LANES];
+                                Response::Ok
+                            }
+                            Ok(None) => Response::Ok,
+                            Err(e) => Response::Error(e),
+                        }
+                    }
+                    // no room for two gallery slots
+                    None => Response::Error(Error::Invalid),
+                },
+                Err(e) => Response::Error(e),
+            };
+            board.reply(port, &response);
+        }
+
+        // what the picture's drawn through, if anything
+        #[cfg(feature = "window")]
+        let pan = Some(&window);
+        #[cfg(not(feature = "window"))]
+        let pan = None;
+
+        // a flush that failed on its way out counts like any other
+        let mut result = None;
+        // one lane at a time, the rest wait for the bus
+        let waiting = (0..lanes).find(|&l| needs_init[l] || needs_draw[l]);
+        if FAILED.swap(false, Ordering::SeqCst) {
+            microaleph::warn!("flush failed");
+            needs_draw[sent] = true;
+            result = Some(Err(DisplayError::BusWriteError));
+        } else if let (Some(lane), false) = (waiting, board.busy()) {
+            sent = lane;
+            let index = show.lane(lane, lanes);
+            microaleph::info!("showing image {=usize} on lane {=usize}", index, lane);
+            // gray frames start over on their high plane
+            let picture = my_plane(&flash, gallery, index, 0);
+            #[cfg(feature = "gray4")]
+            dither.restart();
+            let init = if needs_init[lane] {
+                board.init(lane)
+            } else {
+                Ok(())
+            };
+            result = Some(init.and_then(|_| {
+                my_flush(&mut board, lane, fader.current(), |frame| {
+                    my_draw(&show, &guard, picture, pan, frame)
+                })
+            }));
+        }
+        if let Some(result) = result {
+            match health.record(&result) {
+                Action::Done => {
+                    needs_init[sent] = false;
+                    needs_draw[sent] = false;
+                }
+                Action::Retry => board.delay_ms(10),
+                Action::Recover => {
+                    board.recover();
+                    needs_init = [true; LANES];
+                }
+                Action::GiveUp => {}
+            }
+        }
+
+        // flipping planes on more than one panel is more than the bus can do
+        #[cfg(feature = "gray4")]
+        let shown = !needs_init[0] && !needs_draw[0];
+        #[cfg(feature = "gray4")]
+        if board.elapsed(Timer::Gray) && lanes == 1 && shown && !board.busy() {
+            let gray_up = show.mode == Mode::Image && !guard.exercising();
+            match (gallery, dither.tick()) {
+                (Some(g), Some(p)) if gray_up && g.planes == gray::PLANES => {
+                    let picture = my_plane(&flash, gallery, show.index, p);
+                    let result = my_flush(&mut board, 0, fader.current(), |frame| {
+                        my_draw(&show, &guard, picture, pan, frame)
+                    });
+                    // the usual redraw sorts out retries and recovery
+                    needs_draw[0] = result.is_err();
+                }
+                _ => {}
+            }
+        }
+
+        if health.is_alive() {
+            board.feed();
+        }
+
+        // Nothing to do until the flush is out, the board wakes us when it
+        // is or a port has something
+        if board.busy() {
+            board.idle();
+        }
+    }
+}
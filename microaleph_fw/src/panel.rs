@@ -0,0 +1,71 @@
+//! Which panel it is, picked with a `panel-*` feature, and the bus checks
+//! for the `interface-i2c` and `interface-spi` features. The board's own
+//! `panel.rs` has the pins and builds the [`Displays`].
+
+#[cfg(feature = "interface-i2c")]
+use core::cell::RefCell;
+
+#[cfg(feature = "interface-spi")]
+use display_interface::WriteOnlyDataCommand;
+#[cfg(feature = "interface-i2c")]
+use embedded_hal::blocking::i2c::Write;
+use heapless::Vec;
+use microaleph::oled::{Oled, Panel};
+use microaleph::scan::LANES;
+#[cfg(feature = "interface-i2c")]
+use microaleph::scan::{self, Lane, Target};
+
+const PANELS: usize = cfg!(feature = "panel-ssd1306") as usize
+    + cfg!(feature = "panel-ssd1306-128x32") as usize
+    + cfg!(feature = "panel-ssd1309") as usize
+    + cfg!(feature = "panel-sh1106") as usize;
+const _: () = assert!(PANELS == 1, "pick one of the panel-* features");
+
+#[cfg(feature = "panel-ssd1306")]
+pub const PANEL: Panel = Panel::SSD1306_128X64;
+#[cfg(feature = "panel-ssd1306-128x32")]
+pub const PANEL: Panel = Panel::SSD1306_128X32;
+#[cfg(feature = "panel-ssd1309")]
+pub const PANEL: Panel = Panel::SSD1309_128X64;
+#[cfg(feature = "panel-sh1106")]
+pub const PANEL: Panel = Panel::SH1106_128X64;
+
+#[cfg(all(feature = "interface-i2c", feature = "interface-spi"))]
+compile_error!("pick one of the interface-i2c and interface-spi features");
+
+#[cfg(not(any(feature = "interface-i2c", feature = "interface-spi")))]
+compile_error!("pick one of the interface-i2c and interface-spi features");
+
+/// One per lane, the first shows what the slideshow is on.
+pub type Displays<DI> = Vec<Oled<DI>, LANES>;
+
+/// A display on each panel that answers on `bus`, or on 0x3C if none do
+/// so there's still something to retry and recover. `shared` is where the
+/// bus goes for the lanes to share.
+#[cfg(feature = "interface-i2c")]
+pub fn lanes<I: Write>(
+    mut bus: I,
+    shared: &'static mut RefCell<Option<I>>,
+) -> Displays<Lane<'static, I>> {
+    let mut targets = scan::scan(&mut bus);
+    if targets.is_empty() {
+        microaleph::warn!("no panels found");
+        targets.push(Target::DEFAULT).ok();
+    }
+    microaleph::info!("{=usize} panels", targets.len());
+    *shared = RefCell::new(Some(bus));
+    let shared: &'static RefCell<Option<I>> = shared;
+    targets
+        .iter()
+        .map(|&t| Oled::new(Lane::new(shared, t), PANEL))
+        .collect()
+}
+
+/// The one display on a bus that only has room for one, like SPI with a
+/// chip select each.
+#[cfg(feature = "interface-spi")]
+pub fn single<DI: WriteOnlyDataCommand>(interface: DI) -> Displays<DI> {
+    let mut displays = Displays::new();
+    displays.push(Oled::new(interface, PANEL)).ok();
+    displays
+}